@@ -20,10 +20,62 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program;
 use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::InstructionData;
 use std::convert::Into;
 
 declare_id!("87CMnS1XEzpePDoXa3HwexwacdUMKubdwbVrPF3djoQJ");
 
+// Maximum number of bytes an instruction can return, as in the runtime.
+pub const MAX_RETURN_DATA: usize = 1024;
+
+// Most signers listed individually in execute_transaction's summary log.
+pub const MAX_LOGGED_SIGNERS: usize = 10;
+
+// Where a transaction's lifetime is measured from.
+pub const EXPIRY_FROM_CREATION: u8 = 0;
+pub const EXPIRY_FROM_FIRST_APPROVAL: u8 = 1;
+
+// Most owners a multisig can have. With as many owners, and every per-owner
+// setting in use, a multisig takes Multisig::space(MAX_OWNERS, len) bytes for
+// a description of len bytes, about 9 KB, plus its tag labels, withdrawal
+// references, vaults and allowed programs. Transactions take Transaction::space(MAX_OWNERS, ..) bytes.
+pub const MAX_OWNERS: usize = 64;
+
+// Longest label set_tag_label accepts, in bytes.
+pub const MAX_TAG_LABEL_LEN: usize = 32;
+
+// Longest owner name set_owner_names accepts, in bytes.
+pub const MAX_OWNER_NAME_LEN: usize = 32;
+
+// Longest label create_vault accepts, in bytes.
+pub const MAX_VAULT_LABEL_LEN: usize = 32;
+
+// Seed distinguishing a vault's signer from the multisig signer.
+pub const VAULT_SEED: &[u8] = b"vault";
+
+// Domain separator prefixed to approval messages, see approval_message.
+pub const APPROVAL_MESSAGE_PREFIX: &[u8] = b"serum-multisig:approve:v1";
+
+// Largest serialized transaction the cluster accepts, as in the runtime.
+pub const MAX_TRANSACTION_SIZE: usize = 1232;
+
+// Most instruction data and accounts a proposed instruction can have, within
+// what the runtime lets a cross-program invocation pass. A transaction
+// account never needs more than Transaction::space(MAX_OWNERS,
+// MAX_TX_ACCOUNTS, MAX_TX_DATA_LEN) bytes, about 15 KB with executed_owners,
+// except for batches, which take TransactionInstruction::space more per
+// instruction. That is past what a cross-program invocation can allocate, so
+// clients create such accounts themselves, with the system program, rather
+// than through create_transaction_pda, which sizes the account to the
+// proposal instead.
+pub const MAX_TX_DATA_LEN: usize = 10 * 1024;
+pub const MAX_TX_ACCOUNTS: usize = 64;
+
+// Seconds an emergency withdrawal waits before completing, unless changed.
+pub const DEFAULT_EMERGENCY_DELAY: i64 = 7 * 24 * 60 * 60;
+
 // ***** Program Account ***** //
 #[program]
 pub mod serum_multisig {
@@ -34,13 +86,46 @@ pub mod serum_multisig {
     // Threshold: The threshold of owner votes that msut be reached for consensus
     // Nonce: The PDA address of the Multisig account
     // owner_set_seqno: The initialized value of the number of times the set of owners have changed
+    // Args: The optional settings, see CreateMultisigArgs
     pub fn create_multisig(
         ctx: Context<CreateMultisig>,
         description: String,
         owners: Vec<Pubkey>,
         threshold: u64,
         nonce: u8,
+        args: CreateMultisigArgs,
     ) -> Result<()> {
+        let CreateMultisigArgs {
+            strict,
+            emergency_recipient,
+            weights,
+        } = args;
+        assert_unique_owners(&owners)?;
+        let weights = owner_weights(&owners, weights)?;
+        let total_weight = sum_weights(&owners, &weights)?;
+        if threshold == 0 || threshold > total_weight {
+            return Err(ErrorCode::InvalidThreshold.into());
+        }
+
+        // The signer has to be a program address, only the program can sign
+        // for it. An on-curve result has a private key and is rejected.
+        let multisig_key = ctx.accounts.multisig.to_account_info().key;
+        Pubkey::create_program_address(&[multisig_key.as_ref(), &[nonce]], ctx.program_id)
+            .map_err(|_| ErrorCode::InvalidNonce)?;
+
+        let majority = total_weight / 2 + 1;
+        if threshold < majority {
+            if strict {
+                return Err(ErrorCode::ThresholdBelowMajority.into());
+            }
+            msg!(
+                "Warning: threshold {} is below a majority of the owners' total weight {} ({})",
+                threshold,
+                total_weight,
+                majority
+            );
+        }
+
         let multisig = &mut ctx.accounts.multisig;
         multisig.description = description;
         multisig.owners = owners;
@@ -48,45 +133,476 @@ pub mod serum_multisig {
         multisig.nonce = nonce;
         multisig.owner_set_seqno = 0;
         multisig.lamports = 0;
+        multisig.approve_execute_cooldown = 0;
+        multisig.paused = false;
+        multisig.pause_threshold = 0;
+        multisig.unpause_threshold = 0;
+        multisig.invalidate_on_tamper = false;
+        multisig.alert_mode = false;
+        multisig.transaction_count = 0;
+        multisig.min_slot_delay = 0;
+        multisig.max_mint_amount = 0;
+        multisig.weights = weights;
+        multisig.reweight_invalidates = false;
+        multisig.owner_groups = Vec::new();
+        multisig.min_groups = 0;
+        multisig.transaction_lifetime = 0;
+        multisig.expiry_from = EXPIRY_FROM_CREATION;
+        multisig.executor = None;
+        multisig.pending_executor = None;
+        multisig.pending_executor_deadline = 0;
+        multisig.suspended_owners = Vec::new();
+        multisig.clamp_threshold_to_active = false;
+        multisig.min_reserve = 0;
+        multisig.executor_must_be_owner = false;
+        multisig.emit_per_approval = true;
+        multisig.proposer_cannot_execute = false;
+        multisig.contributions = Vec::new();
+        multisig.weight_by_contribution = false;
+        multisig.reject_trivial_data = false;
+        multisig.tag_labels = Vec::new();
+        multisig.add_owner_requires_unanimous = false;
+        multisig.last_heartbeat_at = 0;
+        multisig.protect_executor = false;
+        multisig.names = Vec::new();
+        multisig.require_withdraw_reference = false;
+        multisig.withdraw_references = Vec::new();
+        multisig.emergency_recipient = emergency_recipient;
+        multisig.emergency_threshold = threshold;
+        multisig.emergency_delay = DEFAULT_EMERGENCY_DELAY;
+        multisig.emergency_approvals = Vec::new();
+        multisig.emergency_initiated_at = 0;
+        multisig.reject_threshold = 0;
+        multisig.timelock = 0;
+        multisig.max_proposal_age = 0;
+        multisig.permanent_owner = None;
+        multisig.max_approvals_per_window = 0;
+        multisig.approval_window = 0;
+        multisig.approvals_this_window = Vec::new();
+        multisig.window_starts = Vec::new();
+        multisig.vaults = Vec::new();
+        multisig.threshold_decrease_delay = 0;
+        multisig.pending_threshold = 0;
+        multisig.pending_threshold_ready_at = 0;
+        multisig.spending_limit = 0;
+        multisig.spending_period = 0;
+        multisig.spent_in_period = 0;
+        multisig.period_start = 0;
+        multisig.allowed_programs = Vec::new();
+        multisig.review_period = 0;
+        emit!(MultisigCreated {
+            multisig: *multisig.to_account_info().key,
+            owners: multisig.owners.clone(),
+            weights: multisig.weights.clone(),
+            threshold: multisig.threshold,
+            description: multisig.description.clone(),
+        });
         Ok(())
     }
 
     // TODO: Document
     // Creates a new transaction account, automatically signed by the creator,
-    // which must be one of the owners of the multisig.
+    // which must be one of the owners of the multisig. The proposer may raise
+    // the threshold the transaction needs above the multisig's, up to its
+    // total weight.
     pub fn create_transaction(
         ctx: Context<CreateTransaction>,
         pid: Pubkey,
         accs: Vec<TransactionAccount>,
         data: Vec<u8>,
+        threshold_override: Option<u64>,
+    ) -> Result<()> {
+        if let Some(threshold) = threshold_override {
+            let multisig = &ctx.accounts.multisig;
+            if threshold < multisig.threshold || threshold > multisig.total_weight() {
+                return Err(ErrorCode::InvalidThreshold.into());
+            }
+        }
+        ctx.accounts.transaction.threshold_override = threshold_override;
+        // Passing the program's program data account as the first remaining
+        // account pins the proposal to the program's current deployment.
+        if let Some(program_data) = ctx.remaining_accounts.first() {
+            ctx.accounts.transaction.target_program_slot =
+                Some(program_deploy_slot(&pid, program_data)?);
+        }
+        init_transaction(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            ctx.accounts.proposer.key,
+            pid,
+            accs,
+            data,
+        )
+    }
+
+    // Runs the checks create_transaction applies to a proposal by the given
+    // proposer, without creating anything. Returns the Borsh encoded
+    // Option<u32> code of the first error creating it would fail with, none
+    // if it would pass. The account size isn't checked, as no account is
+    // given.
+    pub fn validate_proposal(
+        ctx: Context<ValidateProposal>,
+        pid: Pubkey,
+        accs: Vec<TransactionAccount>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let result = if multisig.owners.contains(ctx.accounts.proposer.key) {
+            check_proposal(
+                multisig,
+                multisig.to_account_info().key,
+                &[],
+                &pid,
+                &accs,
+                &data,
+            )
+        } else {
+            Err(ErrorCode::InvalidOwner.into())
+        };
+        let code = match result.map_err(ProgramError::from) {
+            Ok(()) => None,
+            Err(ProgramError::Custom(code)) => Some(code),
+            Err(err) => return Err(err.into()),
+        };
+        let data = code
+            .try_to_vec()
+            .map_err(|err| ProgramError::BorshIoError(err.to_string()))?;
+        set_return_data(&data);
+        Ok(())
+    }
+
+    // Creates a new transaction wrapping several instructions, executed in
+    // order and all or nothing. Its program_id, accounts and data are unused.
+    pub fn create_transaction_batch(
+        ctx: Context<CreateTransaction>,
+        instructions: Vec<TransactionInstruction>,
+    ) -> Result<()> {
+        if instructions.is_empty() {
+            return Err(ErrorCode::EmptyBatch.into());
+        }
+        ctx.accounts.transaction.instructions = instructions;
+        init_transaction(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            ctx.accounts.proposer.key,
+            Pubkey::default(),
+            Vec::new(),
+            Vec::new(),
+        )
+    }
+
+    // Creates a new transaction like create_transaction, but at the program
+    // address derived from [b"tx", multisig, index], so clients can find the
+    // multisig's transactions without tracking keypairs. The index must be the
+    // multisig's current transaction count.
+    pub fn create_transaction_pda(
+        ctx: Context<CreateTransactionPda>,
+        pid: Pubkey,
+        accs: Vec<TransactionAccount>,
+        data: Vec<u8>,
+        index: u64,
+        bump: u8,
+    ) -> Result<()> {
+        if index != ctx.accounts.multisig.transaction_count {
+            return Err(ErrorCode::InvalidTransactionIndex.into());
+        }
+        init_transaction(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            ctx.accounts.proposer.key,
+            pid,
+            accs,
+            data,
+        )?;
+        ctx.accounts.transaction.bump = bump;
+        Ok(())
+    }
+
+    // Creates a new transaction like create_transaction, but one that advances
+    // the given durable nonce account before running the proposal, so the
+    // execution can be signed offline against that nonce. The nonce authority
+    // must be the multisig signer.
+    pub fn create_durable_transaction(
+        ctx: Context<CreateDurableTransaction>,
+        pid: Pubkey,
+        accs: Vec<TransactionAccount>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        assert_nonce_authority(
+            &ctx.accounts.nonce_account,
+            ctx.accounts.multisig_signer.key,
+        )?;
+        init_transaction(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            ctx.accounts.proposer.key,
+            pid,
+            accs,
+            data,
+        )?;
+        ctx.accounts.transaction.durable_nonce = Some(*ctx.accounts.nonce_account.key);
+        Ok(())
+    }
+
+    // Creates a new transaction like create_transaction, but one that can only
+    // be executed with exactly its own accounts, in order and with the same
+    // writability, followed by the program being invoked. Guards against a
+    // client substituting accounts at execution.
+    pub fn create_locked_transaction(
+        ctx: Context<CreateTransaction>,
+        pid: Pubkey,
+        accs: Vec<TransactionAccount>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        init_transaction(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            ctx.accounts.proposer.key,
+            pid,
+            accs,
+            data,
+        )?;
+        ctx.accounts.transaction.accounts_locked = true;
+        Ok(())
+    }
+
+    // TODO: Document
+    // Proposes minting `amount` tokens of `mint` into `destination`, with the
+    // multisig signer as the mint authority. Fails if the amount exceeds the
+    // multisig's mint cap, if one is set.
+    pub fn propose_mint_to(
+        ctx: Context<ProposeTransaction>,
+        mint: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        let max_mint_amount = ctx.accounts.multisig.max_mint_amount;
+        if max_mint_amount > 0 && amount > max_mint_amount {
+            return Err(ErrorCode::MintCapExceeded.into());
+        }
+        let ix = spl_token::instruction::mint_to(
+            &spl_token::ID,
+            &mint,
+            &destination,
+            ctx.accounts.multisig_signer.key,
+            &[],
+            amount,
+        )?;
+        init_transaction_from_ix(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            ctx.accounts.proposer.key,
+            ix,
+        )
+    }
+
+    // Proposes replacing the owners with new_owners, weighted equally, through
+    // this program's set_owners, so clients don't have to encode the call.
+    pub fn propose_set_owners(
+        ctx: Context<ProposeTransaction>,
+        new_owners: Vec<Pubkey>,
+    ) -> Result<()> {
+        let data = instruction::SetOwners {
+            owners: new_owners,
+            weights: Vec::new(),
+        }
+        .data();
+        let ix = governance_ix(ctx.program_id, ctx.accounts, data);
+        init_transaction_from_ix(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            ctx.accounts.proposer.key,
+            ix,
+        )
+    }
+
+    // Proposes changing the threshold through this program's
+    // change_threshold, like propose_set_owners.
+    pub fn propose_change_threshold(
+        ctx: Context<ProposeTransaction>,
+        threshold: u64,
+    ) -> Result<()> {
+        let data = instruction::ChangeThreshold { threshold }.data();
+        let ix = governance_ix(ctx.program_id, ctx.accounts, data);
+        init_transaction_from_ix(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            ctx.accounts.proposer.key,
+            ix,
+        )
+    }
+
+    // Proposes an instruction handing some authority over to the multisig,
+    // e.g. an SPL token set_authority. The multisig_signer's key is written
+    // into data at new_authority_offset, so the proposal can't hand control to
+    // anyone else. The current authority co-signs the execution.
+    pub fn propose_absorb_authority(
+        ctx: Context<ProposeTransaction>,
+        pid: Pubkey,
+        accs: Vec<TransactionAccount>,
+        data: Vec<u8>,
+        new_authority_offset: u32,
+    ) -> Result<()> {
+        let mut data = data;
+        let start = new_authority_offset as usize;
+        let new_authority = start
+            .checked_add(32)
+            .and_then(|end| data.get_mut(start..end))
+            .ok_or(ErrorCode::InvalidAuthorityOffset)?;
+        new_authority.copy_from_slice(ctx.accounts.multisig_signer.key.as_ref());
+        init_transaction(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            ctx.accounts.proposer.key,
+            pid,
+            accs,
+            data,
+        )
+    }
+
+    // Proposes a transaction, approves it on behalf of every owner signing
+    // along as a remaining account, and executes it right away, for when all
+    // the owners needed are at hand. The remaining accounts are passed on to
+    // execute_transaction, which the proposer executes with all its checks,
+    // followed by this program, so they must also hold the wrapped
+    // instruction's accounts and program.
+    pub fn propose_approve_execute<'info>(
+        ctx: Context<'_, '_, '_, 'info, ProposeApproveExecute<'info>>,
+        pid: Pubkey,
+        accs: Vec<TransactionAccount>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        init_transaction(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            ctx.accounts.proposer.key,
+            pid,
+            accs,
+            data,
+        )?;
+        let transaction = *ctx.accounts.transaction.to_account_info().key;
+        for info in ctx.remaining_accounts.iter().filter(|info| info.is_signer) {
+            if let Some(owner_index) = ctx
+                .accounts
+                .multisig
+                .owners
+                .iter()
+                .position(|a| a == info.key)
+            {
+                throttle_approval(&mut ctx.accounts.multisig, owner_index)?;
+                record_approval(
+                    &ctx.accounts.multisig,
+                    &mut ctx.accounts.transaction,
+                    &transaction,
+                    owner_index,
+                )?;
+            }
+        }
+        execute_via_cpi(
+            ctx.program_id,
+            &mut ctx.accounts.multisig,
+            &ctx.accounts.multisig_signer,
+            &mut ctx.accounts.transaction,
+            &ctx.accounts.proposer,
+            ctx.remaining_accounts,
+        )
+    }
+
+    // Approves a transaction on behalf of an owner and, if that reaches the
+    // threshold, executes it right away with the owner as the executor. The
+    // remaining accounts are passed on to execute_transaction as with
+    // propose_approve_execute. Short of the threshold, only the approval is
+    // recorded.
+    pub fn approve_and_execute<'info>(
+        ctx: Context<'_, '_, '_, 'info, ApproveAndExecute<'info>>,
     ) -> Result<()> {
         let owner_index = ctx
             .accounts
             .multisig
             .owners
             .iter()
-            .position(|a| a == ctx.accounts.proposer.key)
+            .position(|a| a == ctx.accounts.owner.key)
+            .ok_or(ErrorCode::InvalidOwner)?;
+        throttle_approval(&mut ctx.accounts.multisig, owner_index)?;
+        let transaction = *ctx.accounts.transaction.to_account_info().key;
+        record_approval(
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            &transaction,
+            owner_index,
+        )?;
+
+        let multisig = &ctx.accounts.multisig;
+        let tx = &ctx.accounts.transaction;
+        if multisig.signer_weight(&tx.signers) < required_threshold(multisig, tx) {
+            return Ok(());
+        }
+        execute_via_cpi(
+            ctx.program_id,
+            &mut ctx.accounts.multisig,
+            &ctx.accounts.multisig_signer,
+            &mut ctx.accounts.transaction,
+            &ctx.accounts.owner,
+            ctx.remaining_accounts,
+        )
+    }
+
+    // TODO: Document
+    // Approves a transaction on behalf of an owner of the multisig.
+    pub fn approve(ctx: Context<Approve>) -> Result<()> {
+        let owner_index = ctx
+            .accounts
+            .multisig
+            .owners
+            .iter()
+            .position(|a| a == ctx.accounts.owner.key)
             .ok_or(ErrorCode::InvalidOwner)?;
+        throttle_approval(&mut ctx.accounts.multisig, owner_index)?;
 
-        let mut signers = Vec::new();
-        signers.resize(ctx.accounts.multisig.owners.len(), false);
-        signers[owner_index] = true;
+        let transaction = *ctx.accounts.transaction.to_account_info().key;
+        record_approval(
+            &ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            &transaction,
+            owner_index,
+        )
+    }
 
+    // Withdraws an owner's approval of a transaction that hasn't been executed
+    // yet. Fails if the owner hasn't approved it. Dropping below the
+    // threshold restarts the slot delay once it's reached again.
+    pub fn revoke(ctx: Context<Approve>) -> Result<()> {
+        let owner_index = ctx
+            .accounts
+            .multisig
+            .owners
+            .iter()
+            .position(|a| a == ctx.accounts.owner.key)
+            .ok_or(ErrorCode::InvalidOwner)?;
+        let multisig = &ctx.accounts.multisig;
         let tx = &mut ctx.accounts.transaction;
-        tx.program_id = pid;
-        tx.accounts = accs;
-        tx.data = data;
-        tx.signers = signers;
-        tx.multisig = *ctx.accounts.multisig.to_account_info().key;
-        tx.did_execute = false;
-        tx.owner_set_seqno = ctx.accounts.multisig.owner_set_seqno;
+        if tx.did_execute {
+            return Err(ErrorCode::AlreadyExecuted.into());
+        }
+        if !tx.signers.get(owner_index) {
+            return Err(ErrorCode::NotApproved.into());
+        }
 
+        tx.signers.clear(owner_index);
+        tx.approved_at[owner_index] = 0;
+        if multisig.signer_weight(&tx.signers) < required_threshold(multisig, tx) {
+            tx.approved_slot = 0;
+            tx.ready_at = 0;
+        }
+        emit_revocation(multisig, tx, ctx.accounts.owner.key, false);
         Ok(())
     }
 
-    // TODO: Document
-    // Approves a transaction on behalf of an owner of the multisig.
-    pub fn approve(ctx: Context<Approve>) -> Result<()> {
+    // Rejects a transaction on behalf of an owner, withdrawing their approval
+    // if they gave one. Approving withdraws the rejection in turn. Once the
+    // rejecters' weight reaches the multisig's reject threshold, or leaves
+    // the threshold out of reach, the transaction can't be executed.
+    pub fn reject(ctx: Context<Approve>) -> Result<()> {
         let owner_index = ctx
             .accounts
             .multisig
@@ -94,9 +610,192 @@ pub mod serum_multisig {
             .iter()
             .position(|a| a == ctx.accounts.owner.key)
             .ok_or(ErrorCode::InvalidOwner)?;
+        let multisig = &ctx.accounts.multisig;
+        let tx = &mut ctx.accounts.transaction;
+        if tx.did_execute {
+            return Err(ErrorCode::AlreadyExecuted.into());
+        }
+
+        tx.rejecters[owner_index] = true;
+        tx.signers.clear(owner_index);
+        tx.approved_at[owner_index] = 0;
+        if multisig.signer_weight(&tx.signers) < required_threshold(multisig, tx) {
+            tx.approved_slot = 0;
+            tx.ready_at = 0;
+        }
+        emit_revocation(multisig, tx, ctx.accounts.owner.key, true);
+        Ok(())
+    }
 
-        ctx.accounts.transaction.signers[owner_index] = true;
+    // Holds a transaction for the multisig's review period, during which it
+    // can't be executed, e.g. after a suspicious burst of approvals. Any
+    // single owner can flag a transaction, but only once.
+    pub fn flag_for_review(ctx: Context<Approve>) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        if !multisig.owners.contains(ctx.accounts.owner.key) {
+            return Err(ErrorCode::InvalidOwner.into());
+        }
+        if multisig.review_period == 0 {
+            return Err(ErrorCode::ReviewNotEnabled.into());
+        }
+        let tx = &mut ctx.accounts.transaction;
+        if tx.did_execute {
+            return Err(ErrorCode::AlreadyExecuted.into());
+        }
+        if tx.review_hold_until != 0 {
+            return Err(ErrorCode::AlreadyFlagged.into());
+        }
+
+        tx.review_hold_until = Clock::get()?
+            .unix_timestamp
+            .saturating_add(multisig.review_period);
+        emit!(TransactionFlagged {
+            multisig: tx.multisig,
+            transaction: *tx.to_account_info().key,
+            owner: *ctx.accounts.owner.key,
+            hold_until: tx.review_hold_until,
+        });
+        Ok(())
+    }
+
+    // Approves a transaction like approve, but only if its content hash is the
+    // one the owner reviewed, so the content can't be swapped before signing.
+    pub fn approve_checked(ctx: Context<Approve>, expected_hash: [u8; 32]) -> Result<()> {
+        if ctx.accounts.transaction.compute_content_hash() != expected_hash {
+            return Err(ErrorCode::ContentHashMismatch.into());
+        }
+        approve(ctx)
+    }
+
+    // Closes a transaction nobody but its proposer approved, refunding its
+    // rent to the proposer, who must be the one deleting it.
+    pub fn delete_transaction(ctx: Context<DeleteTransaction>) -> Result<()> {
+        let tx = &ctx.accounts.transaction;
+        if &tx.proposer != ctx.accounts.proposer.key {
+            return Err(ErrorCode::UnableToDelete.into());
+        }
+        // Counting approvals isn't enough: the proposer may have revoked
+        // theirs before another owner approved.
+        let proposer_index = ctx
+            .accounts
+            .multisig
+            .owners
+            .iter()
+            .position(|a| a == &tx.proposer);
+        if tx
+            .signers
+            .indices()
+            .any(|index| Some(index) != proposer_index)
+        {
+            return Err(ErrorCode::TransactionAlreadySigned.into());
+        }
+        emit!(TransactionDeleted {
+            multisig: tx.multisig,
+            transaction: *tx.to_account_info().key,
+            proposer: tx.proposer,
+        });
+        Ok(())
+    }
+
+    // Approves one transaction in each of several multisigs the owner belongs
+    // to. The remaining accounts are (multisig, transaction) pairs. Pairs
+    // whose multisig the owner isn't a member of are skipped.
+    pub fn approve_multi(ctx: Context<ApproveMulti>) -> Result<()> {
+        if !ctx.remaining_accounts.len().is_multiple_of(2) {
+            return Err(ErrorCode::InvalidMultisigAccount.into());
+        }
+        for pair in ctx.remaining_accounts.chunks(2) {
+            let (multisig_info, tx_info) = (&pair[0], &pair[1]);
+            if multisig_info.owner != ctx.program_id || tx_info.owner != ctx.program_id {
+                return Err(ErrorCode::InvalidMultisigAccount.into());
+            }
+            let mut multisig = Multisig::try_deserialize(&mut &multisig_info.data.borrow()[..])?;
+            let mut tx = Transaction::try_deserialize(&mut &tx_info.data.borrow()[..])?;
+            if &tx.multisig != multisig_info.key {
+                return Err(ErrorCode::InvalidMultisigAccount.into());
+            }
+            if tx.owner_set_seqno != multisig.owner_set_seqno {
+                return Err(ErrorCode::StaleTransaction.into());
+            }
+
+            let owner_index = match multisig
+                .owners
+                .iter()
+                .position(|a| a == ctx.accounts.owner.key)
+            {
+                Some(owner_index) => owner_index,
+                None => {
+                    msg!("{}: not an owner, skipping", multisig_info.key);
+                    continue;
+                }
+            };
+            if multisig.max_approvals_per_window > 0 {
+                throttle_approval(&mut multisig, owner_index)?;
+                write_account(multisig_info, &multisig)?;
+            }
+            record_approval(&multisig, &mut tx, tx_info.key, owner_index)?;
+            write_account(tx_info, &tx)?;
+        }
+        Ok(())
+    }
+
+    // Copies the approvals of the given source transaction onto a transaction
+    // re-proposing the same content under the same owner set. Any owner can
+    // import them. The source must have expired or been cancelled, and its
+    // approvals move rather than copy: it's cancelled and its signers cleared,
+    // so they can't be spent twice. Owners who rejected the transaction since
+    // keep their rejection rather than getting their old approval back.
+    pub fn import_approvals(ctx: Context<ImportApprovals>) -> Result<()> {
+        if !ctx.accounts.multisig.owners.contains(ctx.accounts.owner.key) {
+            return Err(ErrorCode::InvalidOwner.into());
+        }
+        let src = &ctx.accounts.source;
+        if src.to_account_info().key == ctx.accounts.transaction.to_account_info().key {
+            return Err(ErrorCode::InvalidSourceTransaction.into());
+        }
+        // An executed transaction's approvals were spent on its execution.
+        if src.did_execute || ctx.accounts.transaction.did_execute {
+            return Err(ErrorCode::AlreadyExecuted.into());
+        }
+        if src.owner_set_seqno != ctx.accounts.multisig.owner_set_seqno {
+            return Err(ErrorCode::StaleTransaction.into());
+        }
+        let expired = match src.expires_at(ctx.accounts.multisig.transaction_lifetime) {
+            Some(expires_at) => Clock::get()?.unix_timestamp >= expires_at,
+            None => false,
+        };
+        if !expired
+            && src.status != TransactionStatus::Cancelled
+            && src.status != TransactionStatus::Expired
+        {
+            return Err(ErrorCode::InvalidSourceTransaction.into());
+        }
+        if src.compute_content_hash() != ctx.accounts.transaction.compute_content_hash() {
+            return Err(ErrorCode::ContentHashMismatch.into());
+        }
+
+        let src = &mut ctx.accounts.source;
+        let tx = &mut ctx.accounts.transaction;
+        let mut imported = 0;
+        for index in 0..src.approved_at.len() {
+            if src.signers.get(index) && !tx.signers.get(index) && !tx.rejecters[index] {
+                tx.signers.set(index);
+                tx.approved_at[index] = src.approved_at[index];
+                imported += 1;
+            }
+        }
+        src.signers = SignerSet::default();
+        src.approved_at = vec![0; src.approved_at.len()];
+        src.status = TransactionStatus::Cancelled;
+        note_threshold_reached(&ctx.accounts.multisig, tx)?;
 
+        emit!(ApprovalsImported {
+            multisig: tx.multisig,
+            transaction: *tx.to_account_info().key,
+            source: *src.to_account_info().key,
+            owner: *ctx.accounts.owner.key,
+            imported,
+        });
         Ok(())
     }
 
@@ -110,157 +809,3462 @@ pub mod serum_multisig {
         set_owners(
             Context::new(ctx.program_id, ctx.accounts, ctx.remaining_accounts),
             owners,
+            Vec::new(),
         )?;
         change_threshold(ctx, threshold)
     }
 
-    // Sets the owners field on the multisig. The only way this can be invoked
-    // is via a recursive call from execute_transaction -> set_owners.
-    pub fn set_owners(ctx: Context<Auth>, owners: Vec<Pubkey>) -> Result<()> {
+    // Sets the owners field on the multisig, with weights[index] the voting
+    // weight of owners[index], or every owner weighing one if empty. Fails
+    // while owners are weighed by their contributions, which start over with
+    // the new owners and would leave the threshold out of reach. The only
+    // way this can be invoked is via a recursive call from
+    // execute_transaction -> set_owners.
+    pub fn set_owners(ctx: Context<Auth>, owners: Vec<Pubkey>, weights: Vec<u64>) -> Result<()> {
+        assert_unique_owners(&owners)?;
+        let weights = owner_weights(&owners, weights)?;
+        let total_weight = sum_weights(&owners, &weights)?;
         let multisig = &mut ctx.accounts.multisig;
+        if multisig.weight_by_contribution {
+            return Err(ErrorCode::WeighedByContribution.into());
+        }
+        if let Some(permanent_owner) = multisig.permanent_owner {
+            if !owners.contains(&permanent_owner) {
+                return Err(ErrorCode::CannotRemovePermanentOwner.into());
+            }
+        }
 
-        if (owners.len() as u64) < multisig.threshold {
-            multisig.threshold = owners.len() as u64;
+        // Contributions, names and groups belong to the old owners, they
+        // start over and the group spread requirement has to be set up again.
+        multisig.weights = weights;
+        multisig.contributions = Vec::new();
+        multisig.names = Vec::new();
+        multisig.owner_groups = Vec::new();
+        multisig.min_groups = 0;
+        multisig.emergency_approvals = Vec::new();
+        multisig.approvals_this_window = Vec::new();
+        multisig.window_starts = Vec::new();
+
+        if total_weight < multisig.threshold {
+            // Shrinking the owners below the threshold lowers it.
+            if multisig.alert_mode {
+                return Err(ErrorCode::ThresholdDecreaseBlocked.into());
+            }
+            multisig.threshold = total_weight;
         }
+        multisig.pause_threshold = multisig.pause_threshold.min(total_weight);
+        multisig.unpause_threshold = multisig.unpause_threshold.min(total_weight);
 
+        multisig
+            .suspended_owners
+            .retain(|owner| owners.contains(owner));
         multisig.owners = owners;
-        multisig.owner_set_seqno += 1;
+        multisig.owner_set_seqno = multisig
+            .owner_set_seqno
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
 
-        Ok(())
+        snapshot_config(ctx.program_id, multisig, ctx.remaining_accounts)
     }
 
-    // Deposit lamports into the multisig account.
-    // Can only be done recursively through execute_transaction -> deposit_lamports
-    pub fn deposit_lamports(ctx:Context<Escrow>, lamports: u64 )-> Result<()>{
-        Ok(())
-    }
+    // Adds a single owner to the multisig, keeping the existing owners'
+    // weights and contributions. The group spread requirement has to be set up
+    // again, as the new owner has no group. The only way this can be invoked
+    // is via a recursive call from execute_transaction -> add_owner.
+    pub fn add_owner(ctx: Context<Auth>, owner: Pubkey) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.owners.contains(&owner) {
+            return Err(ErrorCode::DuplicateOwner.into());
+        }
+        if multisig.owners.len() >= MAX_OWNERS {
+            return Err(ErrorCode::TooManyOwners.into());
+        }
+        if !multisig.weights.is_empty() {
+            multisig.weights.push(1);
+        }
+        if !multisig.contributions.is_empty() {
+            multisig.contributions.push(0);
+        }
+        if !multisig.names.is_empty() {
+            multisig.names.push(String::new());
+        }
+        if !multisig.emergency_approvals.is_empty() {
+            multisig.emergency_approvals.push(false);
+        }
+        if !multisig.approvals_this_window.is_empty() {
+            multisig.approvals_this_window.push(0);
+            multisig.window_starts.push(0);
+        }
+        multisig.owner_groups = Vec::new();
+        multisig.min_groups = 0;
 
-    // Withdraw lamports to the owner parties.
-    // Can only be done recursively through execute_transaction -> withdraw_lamports
-    pub fn withdraw_lamports(ctx:Context<Escrow>)-> Result<()>{
-        Ok(())
-    }
+        multisig.owners.push(owner);
+        multisig.owner_set_seqno = multisig
+            .owner_set_seqno
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
 
-    // Changes the execution threshold of the multisig. The only way this can be
-    // invoked is via a recursive call from execute_transaction ->
-    // change_threshold.
-    pub fn change_threshold(ctx: Context<Auth>, threshold: u64) -> Result<()> {
-        if threshold > ctx.accounts.multisig.owners.len() as u64 {
-            return Err(ErrorCode::InvalidThreshold.into());
-        }
-        let multisig = &mut ctx.accounts.multisig;
-        multisig.threshold = threshold;
-        Ok(())
+        snapshot_config(ctx.program_id, multisig, ctx.remaining_accounts)
     }
 
-    // TODO: Document
-    // Executes the given transaction if threshold owners have signed it.
-    pub fn execute_transaction(ctx: Context<ExecuteTransaction>) -> Result<()> {
-        // Has this been executed already?
-        if ctx.accounts.transaction.did_execute {
-            return Err(ErrorCode::AlreadyExecuted.into());
-        }
-
-        // Get the count of valid signers on the pending transaction
-        let sig_count = ctx
-            .accounts
-            .transaction
-            .signers
+    // Removes a single owner from the multisig, keeping the remaining owners'
+    // weights and contributions. As with set_owners, a threshold the
+    // remaining owners can't reach is lowered, and the group spread
+    // requirement has to be set up again. While owners are weighed by their
+    // contributions, such a removal fails instead, as lowering the threshold
+    // to what the others contributed would hand the multisig to whoever
+    // deposits next. The only way this can be invoked is via a recursive call
+    // from execute_transaction -> remove_owner.
+    pub fn remove_owner(ctx: Context<Auth>, owner: Pubkey) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let owner_index = multisig
+            .owners
             .iter()
-            .filter(|&did_sign| *did_sign)
-            .count() as u64;
+            .position(|a| a == &owner)
+            .ok_or(ErrorCode::InvalidOwner)?;
+        if multisig.permanent_owner == Some(owner) {
+            return Err(ErrorCode::CannotRemovePermanentOwner.into());
+        }
+        if multisig.owners.len() == 1 {
+            return Err(ErrorCode::LastOwner.into());
+        }
+        if !multisig.weights.is_empty() {
+            multisig.weights.remove(owner_index);
+        }
+        if !multisig.contributions.is_empty() {
+            multisig.contributions.remove(owner_index);
+        }
+        if !multisig.names.is_empty() {
+            multisig.names.remove(owner_index);
+        }
+        if !multisig.emergency_approvals.is_empty() {
+            multisig.emergency_approvals.remove(owner_index);
+        }
+        if !multisig.approvals_this_window.is_empty() {
+            multisig.approvals_this_window.remove(owner_index);
+            multisig.window_starts.remove(owner_index);
+        }
+        multisig.owner_groups = Vec::new();
+        multisig.min_groups = 0;
+        multisig.suspended_owners.retain(|suspended| suspended != &owner);
+        multisig.owners.remove(owner_index);
 
-        // Do we have enough signers on the transaction to execute?    
-        if sig_count < ctx.accounts.multisig.threshold {
-            return Err(ErrorCode::NotEnoughSigners.into());
+        let total_weight = multisig.total_weight();
+        if total_weight < multisig.threshold {
+            if multisig.alert_mode {
+                return Err(ErrorCode::ThresholdDecreaseBlocked.into());
+            }
+            if multisig.weight_by_contribution {
+                return Err(ErrorCode::InvalidThreshold.into());
+            }
+            multisig.threshold = total_weight;
         }
+        multisig.pause_threshold = multisig.pause_threshold.min(total_weight);
+        multisig.unpause_threshold = multisig.unpause_threshold.min(total_weight);
+        multisig.owner_set_seqno = multisig
+            .owner_set_seqno
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
 
-        // Turn the transaction account into a Instruction type
-        let mut ix: Instruction = (&*ctx.accounts.transaction).into();
+        snapshot_config(ctx.program_id, multisig, ctx.remaining_accounts)
+    }
 
-        // Grab the metadata for what accounts should be passed to the instruction processor
-        // In this case we only want the multisig_signer Program Derived Address that we created with the programId and the multisig publicKey
-        ix.accounts = ix
-            .accounts
+    // Replaces an owner with a new key at the same index, e.g. to rotate a
+    // compromised key. The owner set seqno is deliberately left alone, so the
+    // new key inherits the old one's approvals of pending transactions,
+    // including any an attacker made with the old key. acknowledge_risk must
+    // be set to confirm that. The only way this can be invoked is via a
+    // recursive call from execute_transaction -> replace_owner_keep_approvals.
+    pub fn replace_owner_keep_approvals(
+        ctx: Context<Auth>,
+        old: Pubkey,
+        new: Pubkey,
+        acknowledge_risk: bool,
+    ) -> Result<()> {
+        if !acknowledge_risk {
+            return Err(ErrorCode::RiskNotAcknowledged.into());
+        }
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.owners.contains(&new) {
+            return Err(ErrorCode::DuplicateOwner.into());
+        }
+        if multisig.permanent_owner == Some(old) {
+            return Err(ErrorCode::CannotRemovePermanentOwner.into());
+        }
+        let owner_index = multisig
+            .owners
             .iter()
-            .map(|acc| {
-                let mut acc = acc.clone();
-                if &acc.pubkey == ctx.accounts.multisig_signer.key {
-                    acc.is_signer = true;
-                }
-                acc
-            })
-            .collect();
-
-        // Generate the seeds to find the multisig_signer Program Derived Address
-        let seeds = &[
-            ctx.accounts.multisig.to_account_info().key.as_ref(),
-            &[ctx.accounts.multisig.nonce],
-        ];
-
-        // The one signer needed to execute
-        let signer = &[&seeds[..]];
+            .position(|a| a == &old)
+            .ok_or(ErrorCode::InvalidOwner)?;
+        multisig.owners[owner_index] = new;
+        for suspended in multisig.suspended_owners.iter_mut() {
+            if suspended == &old {
+                *suspended = new;
+            }
+        }
 
-        // Grab all of the accounts that havent been touched
-        let accounts = ctx.remaining_accounts;
+        snapshot_config(ctx.program_id, multisig, ctx.remaining_accounts)
+    }
 
-        // Invoke a cross-program instruction with program signatures
-        solana_program::program::invoke_signed(&ix, accounts, signer)?;
+    // Limits how many approvals each owner may give within a window of the
+    // given seconds, zero approvals for no limit, to contain the damage a
+    // compromised key can do. The only way this can be invoked is via a
+    // recursive call from execute_transaction -> set_approval_throttle.
+    pub fn set_approval_throttle(
+        ctx: Context<Auth>,
+        max_approvals_per_window: u64,
+        approval_window: i64,
+    ) -> Result<()> {
+        if approval_window < 0 {
+            return Err(ErrorCode::InvalidExpiry.into());
+        }
+        let multisig = &mut ctx.accounts.multisig;
+        emit!(ApprovalThrottleChanged {
+            multisig: *multisig.to_account_info().key,
+            old_max_approvals_per_window: multisig.max_approvals_per_window,
+            old_approval_window: multisig.approval_window,
+            max_approvals_per_window,
+            approval_window,
+        });
+        multisig.max_approvals_per_window = max_approvals_per_window;
+        multisig.approval_window = approval_window;
+        multisig.approvals_this_window = Vec::new();
+        multisig.window_starts = Vec::new();
+        Ok(())
+    }
 
-        // Burn the transaction to ensure one time use.
-        ctx.accounts.transaction.did_execute = true;
+    // Limits how many lamports can be withdrawn from the escrow within a
+    // period of the given seconds, zero lamports for no limit. Emergency
+    // withdrawals and winding down aren't limited. The only way this can be
+    // invoked is via a recursive call from execute_transaction ->
+    // set_spending_limit.
+    pub fn set_spending_limit(
+        ctx: Context<Auth>,
+        spending_limit: u64,
+        spending_period: i64,
+    ) -> Result<()> {
+        if spending_period < 0 {
+            return Err(ErrorCode::InvalidExpiry.into());
+        }
+        let multisig = &mut ctx.accounts.multisig;
+        emit!(SpendingLimitChanged {
+            multisig: *multisig.to_account_info().key,
+            old_spending_limit: multisig.spending_limit,
+            old_spending_period: multisig.spending_period,
+            spending_limit,
+            spending_period,
+        });
+        multisig.spending_limit = spending_limit;
+        multisig.spending_period = spending_period;
+        multisig.spent_in_period = 0;
+        multisig.period_start = 0;
+        Ok(())
+    }
 
+    // Designates one of the owners as permanent, so no owner change can drop
+    // them. Once designated, the permanent owner can't be changed. The only
+    // way this can be invoked is via a recursive call from
+    // execute_transaction -> set_permanent_owner.
+    pub fn set_permanent_owner(ctx: Context<Auth>, owner: Pubkey) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.permanent_owner.is_some() {
+            return Err(ErrorCode::CannotRemovePermanentOwner.into());
+        }
+        if !multisig.owners.contains(&owner) {
+            return Err(ErrorCode::InvalidOwner.into());
+        }
+        multisig.permanent_owner = Some(owner);
+        emit!(PermanentOwnerSet {
+            multisig: *multisig.to_account_info().key,
+            owner,
+        });
         Ok(())
     }
-}
+
+    // Deposit lamports into the multisig account.
+    // The depositor must be one of the owners and pays for the deposit.
+    pub fn deposit_lamports(ctx: Context<Escrow>, lamports: u64) -> Result<()> {
+        if lamports == 0 {
+            return Err(ErrorCode::DepositTooSmall.into());
+        }
+        if !ctx.accounts.multisig.owners.contains(ctx.accounts.owner.key) {
+            return Err(ErrorCode::InvalidOwner.into());
+        }
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(
+                ctx.accounts.owner.key,
+                ctx.accounts.multisig.to_account_info().key,
+                lamports,
+            ),
+            &[
+                ctx.accounts.owner.clone(),
+                ctx.accounts.multisig.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+        credit_deposit(&mut ctx.accounts.multisig, ctx.accounts.owner.key, lamports)?;
+        emit_deposit(&ctx.accounts.multisig, ctx.accounts.owner.key, lamports);
+        Ok(())
+    }
+
+    // Deposits amount tokens from the owner's source token account into the
+    // multisig signer's token account of the mint. The depositor must be one
+    // of the owners.
+    pub fn deposit_token(ctx: Context<DepositToken>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Err(ErrorCode::DepositTooSmall.into());
+        }
+        if !ctx.accounts.multisig.owners.contains(ctx.accounts.owner.key) {
+            return Err(ErrorCode::InvalidOwner.into());
+        }
+        if ctx.accounts.mint.owner != &spl_token::ID {
+            return Err(ErrorCode::InvalidTokenAccount.into());
+        }
+        signer_token_account(
+            &ctx.accounts.vault,
+            ctx.accounts.multisig_signer.key,
+            ctx.accounts.mint.key,
+        )?;
+
+        let ix = spl_token::instruction::transfer(
+            &spl_token::ID,
+            ctx.accounts.source.key,
+            ctx.accounts.vault.key,
+            ctx.accounts.owner.key,
+            &[],
+            amount,
+        )?;
+        solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.source.clone(),
+                ctx.accounts.vault.clone(),
+                ctx.accounts.owner.clone(),
+                ctx.accounts.token_program.clone(),
+            ],
+        )?;
+        msg!("Deposited {} of {}", amount, ctx.accounts.mint.key);
+        emit!(TokensDeposited {
+            multisig: *ctx.accounts.multisig.to_account_info().key,
+            depositor: *ctx.accounts.owner.key,
+            mint: *ctx.accounts.mint.key,
+            vault: *ctx.accounts.vault.key,
+            amount,
+        });
+        Ok(())
+    }
+
+    // Withdraws amount tokens from the multisig signer's token account of the
+    // mint to the destination token account. The only way this can be
+    // invoked is via a recursive call from execute_transaction ->
+    // withdraw_token.
+    pub fn withdraw_token(ctx: Context<WithdrawToken>, amount: u64) -> Result<()> {
+        if ctx.accounts.mint.owner != &spl_token::ID {
+            return Err(ErrorCode::InvalidTokenAccount.into());
+        }
+        let vault = signer_token_account(
+            &ctx.accounts.vault,
+            ctx.accounts.multisig_signer.key,
+            ctx.accounts.mint.key,
+        )?;
+        if vault.amount < amount {
+            return Err(ErrorCode::InsufficientEscrow.into());
+        }
+
+        let ix = spl_token::instruction::transfer(
+            &spl_token::ID,
+            ctx.accounts.vault.key,
+            ctx.accounts.destination.key,
+            ctx.accounts.multisig_signer.key,
+            &[],
+            amount,
+        )?;
+        solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.vault.clone(),
+                ctx.accounts.destination.clone(),
+                ctx.accounts.multisig_signer.clone(),
+                ctx.accounts.token_program.clone(),
+            ],
+        )?;
+        msg!("Withdrew {} of {}", amount, ctx.accounts.mint.key);
+        emit!(TokensWithdrawn {
+            multisig: *ctx.accounts.multisig.to_account_info().key,
+            mint: *ctx.accounts.mint.key,
+            vault: *ctx.accounts.vault.key,
+            destination: *ctx.accounts.destination.key,
+            amount,
+        });
+        Ok(())
+    }
+
+    // Withdraw lamports to the owner parties, under the given reference.
+    // The whole escrow is split equally among the owners, passed in order as
+    // the remaining accounts, with the remainder going to the first owner.
+    // Can only be done recursively through execute_transaction -> withdraw_lamports
+    pub fn withdraw_lamports(ctx: Context<DistributeEscrow>, reference: [u8; 32]) -> Result<()> {
+        check_withdraw_reference(&ctx.accounts.multisig, &reference)?;
+        let multisig = &mut ctx.accounts.multisig;
+        // The destinations must be the current owners, not those the proposal
+        // was written for.
+        let owners = ctx.remaining_accounts;
+        if owners.is_empty()
+            || owners.len() != multisig.owners.len()
+            || owners
+                .iter()
+                .zip(multisig.owners.iter())
+                .any(|(info, owner)| info.key != owner)
+        {
+            return Err(ErrorCode::OwnerListMismatch.into());
+        }
+        // Draining the escrow can only respect an empty reserve.
+        if multisig.min_reserve > 0 {
+            return Err(ErrorCode::ReserveBreached.into());
+        }
+
+        let total = multisig.lamports;
+        charge_spending(multisig, total)?;
+        let shares = split_escrow(total, owners.len())?;
+        let from = multisig.to_account_info();
+        **from.try_borrow_mut_lamports()? = from
+            .lamports()
+            .checked_sub(total)
+            .ok_or(ErrorCode::InsufficientEscrow)?;
+        for (to, &amount) in owners.iter().zip(shares.iter()) {
+            **to.try_borrow_mut_lamports()? = to
+                .lamports()
+                .checked_add(amount)
+                .ok_or(ErrorCode::Overflow)?;
+            emit!(LamportsWithdrawn {
+                multisig: *from.key,
+                destination: *to.key,
+                amount,
+                reference,
+            });
+        }
+        multisig.lamports = 0;
+        Ok(())
+    }
+
+    // Deposits amount_in lamports into the multisig's escrow and proposes
+    // withdrawing amount_out of them to destination under the given
+    // reference, in one go. The depositor must be one of the owners and
+    // approves the withdrawal as its proposer.
+    pub fn deposit_and_propose_withdrawal(
+        ctx: Context<DepositAndProposeWithdrawal>,
+        amount_in: u64,
+        amount_out: u64,
+        destination: Pubkey,
+        reference: [u8; 32],
+    ) -> Result<()> {
+        let ix = Instruction {
+            program_id: *ctx.program_id,
+            accounts: accounts::WithdrawEscrow {
+                multisig: *ctx.accounts.multisig.to_account_info().key,
+                multisig_signer: *ctx.accounts.multisig_signer.key,
+                destination,
+            }
+            .to_account_metas(None),
+            data: instruction::WithdrawLamportsTo {
+                amount: amount_out,
+                reference,
+            }
+            .data(),
+        };
+        init_transaction_from_ix(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.transaction,
+            ctx.accounts.depositor.key,
+            ix,
+        )?;
+
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(
+                ctx.accounts.depositor.key,
+                ctx.accounts.multisig.to_account_info().key,
+                amount_in,
+            ),
+            &[
+                ctx.accounts.depositor.clone(),
+                ctx.accounts.multisig.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+        credit_deposit(
+            &mut ctx.accounts.multisig,
+            ctx.accounts.depositor.key,
+            amount_in,
+        )?;
+        emit_deposit(
+            &ctx.accounts.multisig,
+            ctx.accounts.depositor.key,
+            amount_in,
+        );
+        Ok(())
+    }
+
+    // Withdraws the given amount of escrowed lamports to the destination,
+    // under the given reference. The only way this can be invoked is via a
+    // recursive call from execute_transaction -> withdraw_lamports_to.
+    pub fn withdraw_lamports_to(
+        ctx: Context<WithdrawEscrow>,
+        amount: u64,
+        reference: [u8; 32],
+    ) -> Result<()> {
+        check_withdraw_reference(&ctx.accounts.multisig, &reference)?;
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.lamports = multisig
+            .lamports
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientEscrow)?;
+        if multisig.lamports < multisig.min_reserve {
+            return Err(ErrorCode::ReserveBreached.into());
+        }
+        charge_spending(multisig, amount)?;
+
+        let from = multisig.to_account_info();
+        let to = &ctx.accounts.destination;
+        **from.try_borrow_mut_lamports()? = from
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientEscrow)?;
+        **to.try_borrow_mut_lamports()? = to
+            .lamports()
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(LamportsWithdrawn {
+            multisig: *from.key,
+            destination: *to.key,
+            amount,
+            reference,
+        });
+        Ok(())
+    }
+
+    // Withdraws the whole escrow to the destination and closes the multisig,
+    // refunding its rent there as well. Every token account owned by the
+    // multisig signer must be passed as a remaining account and be empty.
+    // It always takes the regular threshold, even when clamped to the active
+    // owners. The only way this can be invoked is via a recursive call from
+    // execute_transaction -> wind_down.
+    pub fn wind_down(ctx: Context<WindDown>) -> Result<()> {
+        for account in ctx.remaining_accounts.iter() {
+            if account.owner != &spl_token::ID
+                || account.data_len() != spl_token::state::Account::LEN
+            {
+                return Err(ErrorCode::InvalidAuthorityAccount.into());
+            }
+            let token = spl_token::state::Account::unpack(&account.try_borrow_data()?)?;
+            if &token.owner != ctx.accounts.multisig_signer.key {
+                return Err(ErrorCode::InvalidAuthorityAccount.into());
+            }
+            if token.amount > 0 {
+                return Err(ErrorCode::TokenBalanceRemaining.into());
+            }
+        }
+
+        let multisig = &mut ctx.accounts.multisig;
+        let amount = multisig.lamports;
+        let from = multisig.to_account_info();
+        let to = &ctx.accounts.destination;
+        **from.try_borrow_mut_lamports()? = from
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientEscrow)?;
+        **to.try_borrow_mut_lamports()? = to
+            .lamports()
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        emit!(LamportsWithdrawn {
+            multisig: *from.key,
+            destination: *to.key,
+            amount,
+            reference: [0u8; 32],
+        });
+        multisig.lamports = 0;
+        Ok(())
+    }
+
+    // Closes the multisig, refunding its rent to the destination. Unlike
+    // wind_down, the escrow must have been emptied beforehand. It always
+    // takes the regular threshold too. The only way this can be invoked is
+    // via a recursive call from execute_transaction -> close_multisig.
+    pub fn close_multisig(ctx: Context<WindDown>) -> Result<()> {
+        if ctx.accounts.multisig.lamports != 0 {
+            return Err(ErrorCode::EscrowNotEmpty.into());
+        }
+        emit!(MultisigClosed {
+            multisig: *ctx.accounts.multisig.to_account_info().key,
+            destination: *ctx.accounts.destination.key,
+        });
+        Ok(())
+    }
+
+    // Approves an emergency withdrawal of the whole escrow to the emergency
+    // recipient. Once the approvals reach the emergency threshold the delay
+    // starts, during which any owner can cancel it.
+    pub fn initiate_emergency(ctx: Context<Emergency>) -> Result<()> {
+        let owner = ctx.accounts.owner.key;
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.emergency_recipient == Pubkey::default() {
+            return Err(ErrorCode::NoEmergencyRecipient.into());
+        }
+        if multisig.emergency_initiated_at != 0 {
+            return Err(ErrorCode::EmergencyInProgress.into());
+        }
+        let owner_index = multisig
+            .owners
+            .iter()
+            .position(|a| a == owner)
+            .ok_or(ErrorCode::InvalidOwner)?;
+
+        if multisig.emergency_approvals.len() != multisig.owners.len() {
+            multisig.emergency_approvals = vec![false; multisig.owners.len()];
+        }
+        multisig.emergency_approvals[owner_index] = true;
+        let weight = multisig.approval_weight(&multisig.emergency_approvals);
+        if weight >= multisig.emergency_threshold {
+            multisig.emergency_initiated_at = Clock::get()?.unix_timestamp;
+            msg!(
+                "Emergency withdrawal initiated, completes after {}",
+                multisig
+                    .emergency_initiated_at
+                    .saturating_add(multisig.emergency_delay)
+            );
+        }
+        emit!(EmergencyApproved {
+            multisig: *multisig.to_account_info().key,
+            owner: *owner,
+            approval_weight: weight,
+            initiated_at: multisig.emergency_initiated_at,
+        });
+        Ok(())
+    }
+
+    // Cancels a pending or initiated emergency withdrawal. Any single owner
+    // can do so, which is what makes the lower threshold acceptable.
+    pub fn cancel_emergency(ctx: Context<Emergency>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if !multisig.owners.contains(ctx.accounts.owner.key) {
+            return Err(ErrorCode::InvalidOwner.into());
+        }
+        multisig.emergency_approvals = Vec::new();
+        multisig.emergency_initiated_at = 0;
+        emit!(EmergencyCancelled {
+            multisig: *multisig.to_account_info().key,
+            owner: *ctx.accounts.owner.key,
+        });
+        Ok(())
+    }
+
+    // Completes an initiated emergency withdrawal once its delay has passed,
+    // moving the whole escrow to the emergency recipient. Anyone can call it.
+    pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.emergency_initiated_at == 0 {
+            return Err(ErrorCode::EmergencyNotInitiated.into());
+        }
+        let completes_at = multisig
+            .emergency_initiated_at
+            .checked_add(multisig.emergency_delay)
+            .ok_or(ErrorCode::Overflow)?;
+        if Clock::get()?.unix_timestamp < completes_at {
+            return Err(ErrorCode::EmergencyDelayNotElapsed.into());
+        }
+
+        let amount = multisig.lamports;
+        let from = multisig.to_account_info();
+        let to = &ctx.accounts.emergency_recipient;
+        **from.try_borrow_mut_lamports()? = from
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientEscrow)?;
+        **to.try_borrow_mut_lamports()? = to
+            .lamports()
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        emit!(LamportsWithdrawn {
+            multisig: *from.key,
+            destination: *to.key,
+            amount,
+            reference: [0u8; 32],
+        });
+        multisig.lamports = 0;
+        multisig.emergency_approvals = Vec::new();
+        multisig.emergency_initiated_at = 0;
+        Ok(())
+    }
+
+    // Sets the approval weight needed to initiate an emergency withdrawal,
+    // at most the regular threshold, and the seconds it then waits. The only
+    // way this can be invoked is via a recursive call from
+    // execute_transaction -> set_emergency_policy.
+    pub fn set_emergency_policy(ctx: Context<Auth>, threshold: u64, delay: i64) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if threshold == 0 || threshold > multisig.threshold {
+            return Err(ErrorCode::InvalidThreshold.into());
+        }
+        if delay < 0 {
+            return Err(ErrorCode::InvalidEmergencyDelay.into());
+        }
+        emit!(EmergencyPolicyChanged {
+            multisig: *multisig.to_account_info().key,
+            old_threshold: multisig.emergency_threshold,
+            old_delay: multisig.emergency_delay,
+            threshold,
+            delay,
+        });
+        multisig.emergency_threshold = threshold;
+        multisig.emergency_delay = delay;
+        Ok(())
+    }
+
+    // Sets the withdrawal reference policy: whether withdrawals need a
+    // non-zero reference and, unless empty, the only references they may
+    // use. The only way this can be invoked is via a recursive call from
+    // execute_transaction -> set_withdraw_reference_policy.
+    pub fn set_withdraw_reference_policy(
+        ctx: Context<Auth>,
+        required: bool,
+        references: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        emit!(WithdrawReferencePolicyChanged {
+            multisig: *multisig.to_account_info().key,
+            old_required: multisig.require_withdraw_reference,
+            old_references: multisig.withdraw_references.clone(),
+            required,
+            references: references.clone(),
+        });
+        multisig.require_withdraw_reference = required;
+        multisig.withdraw_references = references;
+        Ok(())
+    }
+
+    // Restricts the programs transactions may target to the given ones, any
+    // if empty. This program always remains allowed, so the list can still be
+    // changed. The only way this can be invoked is via a recursive call from
+    // execute_transaction -> set_allowed_programs.
+    pub fn set_allowed_programs(ctx: Context<Auth>, programs: Vec<Pubkey>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let old = std::mem::replace(&mut multisig.allowed_programs, programs.clone());
+        emit!(AllowedProgramsChanged {
+            multisig: *multisig.to_account_info().key,
+            old_programs: old,
+            programs,
+        });
+        Ok(())
+    }
+
+    // Moves the given amount of escrowed lamports into another multisig's
+    // escrow, crediting its balance. The only way this can be invoked is via
+    // a recursive call from execute_transaction -> transfer_escrow.
+    pub fn transfer_escrow(ctx: Context<TransferEscrow>, amount: u64) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.lamports = multisig
+            .lamports
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientEscrow)?;
+        if multisig.lamports < multisig.min_reserve {
+            return Err(ErrorCode::ReserveBreached.into());
+        }
+        charge_spending(multisig, amount)?;
+        let destination = &mut ctx.accounts.destination;
+        destination.lamports = destination
+            .lamports
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let from = multisig.to_account_info();
+        let to = destination.to_account_info();
+        **from.try_borrow_mut_lamports()? = from
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientEscrow)?;
+        **to.try_borrow_mut_lamports()? = to
+            .lamports()
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        emit!(EscrowTransferred {
+            multisig: *from.key,
+            destination: *to.key,
+            amount,
+        });
+        Ok(())
+    }
+
+    // Changes the execution threshold of the multisig. Increases apply at
+    // once, superseding any pending decrease. With a threshold decrease delay
+    // set, decreases are only recorded, to be applied by
+    // apply_threshold_change once the delay has passed. The only way this can
+    // be invoked is via a recursive call from execute_transaction ->
+    // change_threshold.
+    pub fn change_threshold(ctx: Context<Auth>, threshold: u64) -> Result<()> {
+        if threshold == 0 || threshold > ctx.accounts.multisig.total_weight() {
+            return Err(ErrorCode::InvalidThreshold.into());
+        }
+        if ctx.accounts.multisig.alert_mode && threshold < ctx.accounts.multisig.threshold {
+            return Err(ErrorCode::ThresholdDecreaseBlocked.into());
+        }
+        let multisig = &mut ctx.accounts.multisig;
+        if !set_threshold(multisig, threshold)? {
+            return Ok(());
+        }
+        snapshot_config(ctx.program_id, multisig, ctx.remaining_accounts)
+    }
+
+    // Applies a threshold decrease recorded by change_threshold once the
+    // threshold decrease delay has passed. Any single owner can apply it.
+    pub fn apply_threshold_change(ctx: Context<OwnerAction>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if !multisig.owners.contains(ctx.accounts.owner.key) {
+            return Err(ErrorCode::InvalidOwner.into());
+        }
+        if multisig.pending_threshold_ready_at == 0 {
+            return Err(ErrorCode::NoPendingThresholdChange.into());
+        }
+        if Clock::get()?.unix_timestamp < multisig.pending_threshold_ready_at {
+            return Err(ErrorCode::ThresholdChangePending.into());
+        }
+        // The owners may have changed in the meantime.
+        if multisig.pending_threshold > multisig.total_weight() {
+            return Err(ErrorCode::InvalidThreshold.into());
+        }
+        if multisig.alert_mode {
+            return Err(ErrorCode::ThresholdDecreaseBlocked.into());
+        }
+        multisig.threshold = multisig.pending_threshold;
+        multisig.pending_threshold = 0;
+        multisig.pending_threshold_ready_at = 0;
+        snapshot_config(ctx.program_id, multisig, ctx.remaining_accounts)
+    }
+
+    // Sets the number of seconds a threshold decrease waits before it can be
+    // applied. Zero applies decreases at once. The only way this can be
+    // invoked is via a recursive call from execute_transaction ->
+    // set_threshold_decrease_delay.
+    pub fn set_threshold_decrease_delay(ctx: Context<Auth>, delay: i64) -> Result<()> {
+        if delay < 0 {
+            return Err(ErrorCode::InvalidTimelock.into());
+        }
+        let multisig = &mut ctx.accounts.multisig;
+        let old = multisig.threshold_decrease_delay;
+        multisig.threshold_decrease_delay = delay;
+        emit!(ThresholdDecreaseDelayChanged {
+            multisig: *multisig.to_account_info().key,
+            old_delay: old,
+            delay,
+        });
+        Ok(())
+    }
+
+    // Changes the multisig's description. The only way this can be invoked
+    // is via a recursive call from execute_transaction -> rename.
+    pub fn rename(ctx: Context<Auth>, description: String) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.description = description;
+        snapshot_config(ctx.program_id, multisig, ctx.remaining_accounts)
+    }
+
+    // Transfers the whole balance of a token account of the given mint held by
+    // the multisig signer to the destination, e.g. tokens sent to the signer
+    // by mistake. The only way this can be invoked is via a recursive call
+    // from execute_transaction -> recover_token.
+    pub fn recover_token(ctx: Context<RecoverToken>, mint: Pubkey) -> Result<()> {
+        let token_account = &ctx.accounts.token_account;
+        let token = signer_token_account(token_account, ctx.accounts.multisig_signer.key, &mint)?;
+
+        let ix = spl_token::instruction::transfer(
+            &spl_token::ID,
+            token_account.key,
+            ctx.accounts.destination.key,
+            ctx.accounts.multisig_signer.key,
+            &[],
+            token.amount,
+        )?;
+        solana_program::program::invoke(
+            &ix,
+            &[
+                token_account.clone(),
+                ctx.accounts.destination.clone(),
+                ctx.accounts.multisig_signer.clone(),
+                ctx.accounts.token_program.clone(),
+            ],
+        )?;
+        msg!("Recovered {} of {}", token.amount, mint);
+        emit!(TokensWithdrawn {
+            multisig: *ctx.accounts.multisig.to_account_info().key,
+            mint,
+            vault: *token_account.key,
+            destination: *ctx.accounts.destination.key,
+            amount: token.amount,
+        });
+        Ok(())
+    }
+
+    // Moves the multisig to the signer derived with the given nonce, handing
+    // it the authority over the SPL mints and token accounts passed as
+    // remaining accounts. Anything else the old signer controls has to be
+    // handed over beforehand. The only way this can be invoked is via a
+    // recursive call from execute_transaction -> rotate_signer.
+    pub fn rotate_signer<'info>(
+        ctx: Context<'_, '_, '_, 'info, RotateSigner<'info>>,
+        nonce: u8,
+    ) -> Result<()> {
+        let multisig_key = ctx.accounts.multisig.to_account_info().key;
+        let new_signer =
+            Pubkey::create_program_address(&[multisig_key.as_ref(), &[nonce]], ctx.program_id)
+                .map_err(|_| ErrorCode::InvalidNonce)?;
+        if ctx.accounts.new_multisig_signer.key != &new_signer
+            || ctx.accounts.multisig_signer.key == &new_signer
+        {
+            return Err(ErrorCode::InvalidNonce.into());
+        }
+
+        repoint_authorities(
+            ctx.remaining_accounts,
+            &ctx.accounts.multisig_signer,
+            &new_signer,
+            &ctx.accounts.token_program,
+        )?;
+        ctx.accounts.multisig.nonce = nonce;
+        emit!(SignerRotated {
+            multisig: *multisig_key,
+            old_signer: *ctx.accounts.multisig_signer.key,
+            new_signer,
+        });
+        Ok(())
+    }
+
+    // Initializes an empty config history for the multisig. set_owners,
+    // change_threshold and rename append to it when it's passed to them as
+    // their first remaining account.
+    pub fn create_config_history(ctx: Context<CreateConfigHistory>) -> Result<()> {
+        let history = &mut ctx.accounts.history;
+        history.multisig = *ctx.accounts.multisig.to_account_info().key;
+        history.entries = Vec::new();
+        emit!(ConfigHistoryCreated {
+            multisig: history.multisig,
+            history: *history.to_account_info().key,
+        });
+        Ok(())
+    }
+
+    // Freezes a pending transaction so it can't be executed until it is
+    // unfrozen, without deleting it or clearing its approvals. The only way
+    // this can be invoked is via a recursive call from execute_transaction ->
+    // freeze_transaction.
+    pub fn freeze_transaction(ctx: Context<TransactionAuth>) -> Result<()> {
+        let tx = &mut ctx.accounts.transaction;
+        if tx.did_execute {
+            return Err(ErrorCode::AlreadyExecuted.into());
+        }
+        tx.frozen = true;
+        emit!(TransactionFrozen {
+            multisig: tx.multisig,
+            transaction: *tx.to_account_info().key,
+            frozen: true,
+        });
+        Ok(())
+    }
+
+    // Lifts a freeze placed by freeze_transaction. The only way this can be
+    // invoked is via a recursive call from execute_transaction ->
+    // unfreeze_transaction.
+    pub fn unfreeze_transaction(ctx: Context<TransactionAuth>) -> Result<()> {
+        let tx = &mut ctx.accounts.transaction;
+        tx.frozen = false;
+        emit!(TransactionFrozen {
+            multisig: tx.multisig,
+            transaction: *tx.to_account_info().key,
+            frozen: false,
+        });
+        Ok(())
+    }
+
+    // Sets how long, in seconds, the owner casting the latest approval is
+    // barred from executing the transaction themselves. Zero disables the
+    // check. The only way this can be invoked is via a recursive call from
+    // execute_transaction -> set_approve_execute_cooldown.
+    pub fn set_approve_execute_cooldown(ctx: Context<Auth>, cooldown: i64) -> Result<()> {
+        if cooldown < 0 {
+            return Err(ErrorCode::InvalidTimelock.into());
+        }
+        ctx.accounts.multisig.approve_execute_cooldown = cooldown;
+        emit_execution_delays(&ctx.accounts.multisig);
+        Ok(())
+    }
+
+    // Pauses or unpauses the multisig. While paused, no transaction other than
+    // a set_pause one can be executed. The only way this can be invoked is via
+    // a recursive call from execute_transaction -> set_pause.
+    pub fn set_pause(ctx: Context<Auth>, paused: bool) -> Result<()> {
+        ctx.accounts.multisig.paused = paused;
+        emit!(PauseChanged {
+            multisig: *ctx.accounts.multisig.to_account_info().key,
+            paused,
+        });
+        Ok(())
+    }
+
+    // Sets the number of approvals needed to execute a set_pause transaction
+    // that pauses and one that unpauses the multisig, respectively. Zero
+    // falls back to the regular threshold. The only way this can be invoked
+    // is via a recursive call from execute_transaction -> set_pause_thresholds.
+    pub fn set_pause_thresholds(
+        ctx: Context<Auth>,
+        pause_threshold: u64,
+        unpause_threshold: u64,
+    ) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let total_weight = multisig.total_weight();
+        if pause_threshold > total_weight || unpause_threshold > total_weight {
+            return Err(ErrorCode::InvalidThreshold.into());
+        }
+        emit!(PauseThresholdsChanged {
+            multisig: *multisig.to_account_info().key,
+            old_pause_threshold: multisig.pause_threshold,
+            old_unpause_threshold: multisig.unpause_threshold,
+            pause_threshold,
+            unpause_threshold,
+        });
+        multisig.pause_threshold = pause_threshold;
+        multisig.unpause_threshold = unpause_threshold;
+        Ok(())
+    }
+
+    // Audits a transaction for internal consistency with its multisig, logging
+    // every anomaly found. Fails if the signer set doesn't line up with the
+    // owners or the execution state is impossible.
+    pub fn validate_transaction_integrity(ctx: Context<InspectTransaction>) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let tx = &ctx.accounts.transaction;
+        let owners = multisig.owners.len();
+        let mut healthy = true;
+
+        if tx.approved_at.len() != owners {
+            msg!(
+                "Anomaly: {} approval slots for {} owners",
+                tx.approved_at.len(),
+                owners
+            );
+            healthy = false;
+        }
+        for index in tx.signers.indices().filter(|index| *index >= owners) {
+            msg!("Anomaly: approval at index {} beyond the owners", index);
+            healthy = false;
+        }
+        if tx.owner_set_seqno > multisig.owner_set_seqno {
+            msg!(
+                "Anomaly: owner set seqno {} is ahead of the multisig's {}",
+                tx.owner_set_seqno,
+                multisig.owner_set_seqno
+            );
+            healthy = false;
+        } else if tx.owner_set_seqno != multisig.owner_set_seqno {
+            msg!("Note: the owner set changed since the transaction was created");
+        }
+        if tx.did_execute && tx.signers.count() == 0 {
+            msg!("Anomaly: executed without any approvals");
+            healthy = false;
+        }
+        if tx.did_execute && tx.frozen {
+            msg!("Anomaly: executed while frozen");
+            healthy = false;
+        }
+
+        if !healthy {
+            return Err(ErrorCode::SignerCountMismatch.into());
+        }
+        msg!("Transaction is consistent");
+        Ok(())
+    }
+
+    // Chooses what execute_transaction does when a transaction's content no
+    // longer matches the hash taken at creation: fail (the default), or clear
+    // all approvals so the owners have to re-approve the new content. The only
+    // way this can be invoked is via a recursive call from execute_transaction
+    // -> set_invalidate_on_tamper.
+    pub fn set_invalidate_on_tamper(ctx: Context<Auth>, invalidate: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let old = multisig.invalidate_on_tamper;
+        multisig.invalidate_on_tamper = invalidate;
+        emit!(InvalidateOnTamperChanged {
+            multisig: *multisig.to_account_info().key,
+            old_invalidate: old,
+            invalidate,
+        });
+        Ok(())
+    }
+
+    // Returns a Borsh serialized ProposalSnapshot of the transaction, so
+    // tooling can replay the proposal elsewhere from a single call.
+    pub fn serialize_proposal(ctx: Context<InspectTransaction>) -> Result<()> {
+        let tx = &ctx.accounts.transaction;
+        let snapshot = ProposalSnapshot {
+            program_id: tx.program_id,
+            accounts: tx.accounts.clone(),
+            data: tx.data.clone(),
+            signers: tx.signers,
+            threshold: required_threshold(&ctx.accounts.multisig, tx),
+        };
+        let data = snapshot
+            .try_to_vec()
+            .map_err(|err| ProgramError::BorshIoError(err.to_string()))?;
+        if data.len() > MAX_RETURN_DATA {
+            return Err(ErrorCode::ReturnDataTooLarge.into());
+        }
+        set_return_data(&data);
+        Ok(())
+    }
+
+    // Logs the approval_message owners sign off chain to approve the
+    // transaction under its owner set, base64 encoded, as
+    // `approval message: <message>`, and returns it as return data.
+    pub fn log_approval_message(ctx: Context<InspectTransaction>) -> Result<()> {
+        let message = approval_message(
+            ctx.accounts.multisig.to_account_info().key,
+            ctx.accounts.transaction.to_account_info().key,
+            ctx.accounts.transaction.owner_set_seqno,
+        );
+        msg!("approval message: {}", base64::encode(message));
+        set_return_data(&message);
+        Ok(())
+    }
+
+    // Logs the estimated size of a transaction executing this one, warning if
+    // it's likely over the cluster's limit, in which case the proposal can't
+    // be executed and should be split up.
+    pub fn check_execution_size(ctx: Context<InspectTransaction>) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let multisig_signer = Pubkey::create_program_address(
+            &[multisig.to_account_info().key.as_ref(), &[multisig.nonce]],
+            ctx.program_id,
+        )
+        .map_err(|_| ErrorCode::InvalidNonce)?;
+        let size = ctx.accounts.transaction.execution_size(&multisig_signer);
+        msg!(
+            "Estimated execution size: {} of {} bytes",
+            size,
+            MAX_TRANSACTION_SIZE
+        );
+        if size > MAX_TRANSACTION_SIZE {
+            msg!(
+                "Warning: execution likely exceeds the transaction size limit, \
+                 split the proposal into smaller ones"
+            );
+        }
+        Ok(())
+    }
+
+    // Returns the instructions execute_transaction would invoke, with the
+    // multisig signer marked as a signer, as a Borsh serialized
+    // Vec<TransactionInstruction>. Payloads beyond the return data limit are
+    // returned in pages starting at the given byte offset, the total length
+    // is logged as `cpi payload: <n> bytes`.
+    pub fn dump_cpi_payload(ctx: Context<InspectTransaction>, offset: u32) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let multisig_signer = Pubkey::create_program_address(
+            &[multisig.to_account_info().key.as_ref(), &[multisig.nonce]],
+            ctx.program_id,
+        )
+        .map_err(|_| ErrorCode::InvalidNonce)?;
+        let mut ixs: Vec<Instruction> = (&*ctx.accounts.transaction).into();
+        escalate_signer(&mut ixs, &multisig_signer);
+
+        let payload = ixs
+            .iter()
+            .map(TransactionInstruction::from)
+            .collect::<Vec<_>>()
+            .try_to_vec()
+            .map_err(|err| ProgramError::BorshIoError(err.to_string()))?;
+        msg!("cpi payload: {} bytes", payload.len());
+        let start = (offset as usize).min(payload.len());
+        let end = start.saturating_add(MAX_RETURN_DATA).min(payload.len());
+        set_return_data(&payload[start..end]);
+        Ok(())
+    }
+
+    // Closes every executed transaction of the multisig passed as a remaining
+    // account, refunding their rent to the collector. Transactions that
+    // haven't been executed or belong to another multisig are skipped.
+    pub fn prune_executed(ctx: Context<PruneExecuted>) -> Result<()> {
+        if !ctx.accounts.multisig.owners.contains(ctx.accounts.owner.key) {
+            return Err(ErrorCode::InvalidOwner.into());
+        }
+        let multisig = ctx.accounts.multisig.to_account_info().key;
+        let collector = &ctx.accounts.collector;
+        for info in ctx.remaining_accounts.iter() {
+            if info.owner != ctx.program_id {
+                return Err(ErrorCode::InvalidTransactionAccount.into());
+            }
+            let tx = Transaction::try_deserialize(&mut &info.data.borrow()[..])?;
+            if &tx.multisig != multisig {
+                msg!("{}: belongs to another multisig, skipping", info.key);
+                continue;
+            }
+            if !tx.did_execute {
+                msg!("{}: not executed, skipping", info.key);
+                continue;
+            }
+
+            **collector.try_borrow_mut_lamports()? = collector
+                .lamports()
+                .checked_add(info.lamports())
+                .ok_or(ErrorCode::Overflow)?;
+            **info.try_borrow_mut_lamports()? = 0;
+            info.try_borrow_mut_data()?.iter_mut().for_each(|byte| *byte = 0);
+            emit!(TransactionPruned {
+                multisig: *multisig,
+                transaction: *info.key,
+                collector: *collector.key,
+            });
+        }
+        Ok(())
+    }
+
+    // Logs how many owners approved the transaction and how much approval it
+    // still needs, as `signed: <count> remaining: <weight>`. The remainder is
+    // in weight, which is the number of approvals unless owners are weighted
+    // or suspended.
+    pub fn get_approvals_remaining(ctx: Context<InspectTransaction>) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let tx = &ctx.accounts.transaction;
+        let remaining =
+            required_threshold(multisig, tx).saturating_sub(multisig.signer_weight(&tx.signers));
+        msg!("signed: {} remaining: {}", tx.approval_count(), remaining);
+        Ok(())
+    }
+
+    // Logs the transaction's signers packed into a bitmap of MAX_OWNERS bits,
+    // base64 encoded, and the number of owners, as
+    // `bitmap: <bitmap> owners: <count>`. Bit i % 8 of byte i / 8 is set iff
+    // owners[i] signed.
+    pub fn get_signer_bitmap(ctx: Context<InspectTransaction>) -> Result<()> {
+        let owners = ctx.accounts.transaction.approved_at.len();
+        let bitmap = ctx.accounts.transaction.signers.bits.to_le_bytes();
+        msg!("bitmap: {} owners: {}", base64::encode(bitmap), owners);
+        Ok(())
+    }
+
+    // Logs, for each multisig passed as a remaining account, whether the
+    // candidate is one of its owners.
+    pub fn is_owner_multi(ctx: Context<IsOwnerMulti>, candidate: Pubkey) -> Result<()> {
+        for account in ctx.remaining_accounts.iter() {
+            if account.owner != ctx.program_id {
+                return Err(ErrorCode::InvalidMultisigAccount.into());
+            }
+            let multisig = Multisig::try_deserialize(&mut &account.data.borrow()[..])?;
+            if multisig.owners.contains(&candidate) {
+                msg!("{}: owner", account.key);
+            } else {
+                msg!("{}: not owner", account.key);
+            }
+        }
+        Ok(())
+    }
+
+    // Logs how many of the multisig's transactions passed as remaining
+    // accounts are active, executed, expired or rejected, as
+    // `active: <n> executed: <n> expired: <n> rejected: <n>`. Transactions
+    // approved under a previous owner set count as expired.
+    pub fn get_proposal_stats(ctx: Context<ProposalStats>) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let multisig_key = multisig.to_account_info().key;
+        let now = Clock::get()?.unix_timestamp;
+        let (mut active, mut executed, mut expired, mut rejected) = (0, 0, 0, 0);
+        for info in ctx.remaining_accounts.iter() {
+            if info.owner != ctx.program_id {
+                return Err(ErrorCode::InvalidTransactionAccount.into());
+            }
+            let tx = Transaction::try_deserialize(&mut &info.data.borrow()[..])?;
+            if &tx.multisig != multisig_key {
+                return Err(ErrorCode::InvalidTransactionAccount.into());
+            }
+
+            let age_exceeded = multisig.max_proposal_age > 0
+                && now.saturating_sub(tx.created_at) > multisig.max_proposal_age;
+            let lifetime_exceeded = tx
+                .expires_at(multisig.transaction_lifetime)
+                .is_some_and(|expires_at| now >= expires_at);
+            if tx.did_execute {
+                executed += 1;
+            } else if is_rejected(multisig, &tx) {
+                rejected += 1;
+            } else if tx.owner_set_seqno != multisig.owner_set_seqno
+                || lifetime_exceeded
+                || age_exceeded
+            {
+                expired += 1;
+            } else {
+                active += 1;
+            }
+        }
+        msg!(
+            "active: {} executed: {} expired: {} rejected: {}",
+            active,
+            executed,
+            expired,
+            rejected
+        );
+        Ok(())
+    }
+
+    // Checks the owners for duplicates and for program addresses of the
+    // multisig itself, its signer and vaults, logging each issue. Other
+    // off-curve keys can't be told apart on chain, the runtime has no curve
+    // check. Returns a single byte, 1 if no issues were found and 0 if some
+    // were.
+    pub fn audit_owners(ctx: Context<AuditOwners>) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let multisig_key = multisig.to_account_info().key;
+        let mut program_addresses = vec![Pubkey::create_program_address(
+            &[multisig_key.as_ref(), &[multisig.nonce]],
+            ctx.program_id,
+        )
+        .map_err(|_| ErrorCode::InvalidNonce)?];
+        for index in 0..multisig.vaults.len() {
+            program_addresses.push(multisig.vault(multisig_key, index as u8)?.0);
+        }
+
+        let mut issues = 0;
+        for (index, owner) in multisig.owners.iter().enumerate() {
+            if multisig.owners[..index].contains(owner) {
+                msg!("Duplicate owner {}: {}", index, owner);
+                issues += 1;
+            }
+            if program_addresses.contains(owner) {
+                msg!("Off-curve owner {}: {}", index, owner);
+                issues += 1;
+            }
+        }
+        msg!("Owner audit found {} issues", issues);
+        set_return_data(&[(issues == 0) as u8]);
+        Ok(())
+    }
+
+    // Turns alert mode on. While on, the threshold can only be raised. Any
+    // single owner can turn it on, so it can be raised quickly during an
+    // incident, but turning it off takes a multisig transaction, see
+    // clear_alert_mode.
+    pub fn set_alert_mode(ctx: Context<OwnerAction>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if !multisig.owners.contains(ctx.accounts.owner.key) {
+            return Err(ErrorCode::InvalidOwner.into());
+        }
+        multisig.alert_mode = true;
+        emit!(AlertModeChanged {
+            multisig: *multisig.to_account_info().key,
+            alert_mode: true,
+        });
+        Ok(())
+    }
+
+    // Turns alert mode off. The only way this can be invoked is via a
+    // recursive call from execute_transaction -> clear_alert_mode.
+    pub fn clear_alert_mode(ctx: Context<Auth>) -> Result<()> {
+        ctx.accounts.multisig.alert_mode = false;
+        emit!(AlertModeChanged {
+            multisig: *ctx.accounts.multisig.to_account_info().key,
+            alert_mode: false,
+        });
+        Ok(())
+    }
+
+    // Sets the number of slots that must pass between a transaction reaching
+    // its threshold and its execution. Zero disables the delay. The only way
+    // this can be invoked is via a recursive call from execute_transaction ->
+    // set_min_slot_delay.
+    pub fn set_min_slot_delay(ctx: Context<Auth>, min_slot_delay: u64) -> Result<()> {
+        ctx.accounts.multisig.min_slot_delay = min_slot_delay;
+        emit_execution_delays(&ctx.accounts.multisig);
+        Ok(())
+    }
+
+    // Sets the number of seconds that must pass between a transaction
+    // reaching its threshold and its execution. Zero disables the timelock.
+    // The only way this can be invoked is via a recursive call from
+    // execute_transaction -> set_timelock.
+    pub fn set_timelock(ctx: Context<Auth>, timelock: i64) -> Result<()> {
+        if timelock < 0 {
+            return Err(ErrorCode::InvalidTimelock.into());
+        }
+        ctx.accounts.multisig.timelock = timelock;
+        emit_execution_delays(&ctx.accounts.multisig);
+        Ok(())
+    }
+
+    // Changes the threshold and the minimum slot delay together, so neither is
+    // in effect without the other. Pending transactions are invalidated, as
+    // they were approved under the old rules. A threshold decrease waits out
+    // the threshold decrease delay like one through change_threshold. The
+    // only way this can be invoked is via a recursive call from
+    // execute_transaction -> harden_governance.
+    pub fn harden_governance(
+        ctx: Context<Auth>,
+        new_threshold: u64,
+        min_delay: u64,
+    ) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if new_threshold == 0 || new_threshold > multisig.total_weight() {
+            return Err(ErrorCode::InvalidThreshold.into());
+        }
+        if multisig.alert_mode && new_threshold < multisig.threshold {
+            return Err(ErrorCode::ThresholdDecreaseBlocked.into());
+        }
+        set_threshold(multisig, new_threshold)?;
+        multisig.min_slot_delay = min_delay;
+        emit_execution_delays(multisig);
+        multisig.owner_set_seqno = multisig
+            .owner_set_seqno
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+        snapshot_config(ctx.program_id, multisig, ctx.remaining_accounts)
+    }
+
+    // Caps the amount a single propose_mint_to proposal can mint. Zero removes
+    // the cap. The only way this can be invoked is via a recursive call from
+    // execute_transaction -> set_max_mint_amount.
+    pub fn set_max_mint_amount(ctx: Context<Auth>, max_mint_amount: u64) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let old = multisig.max_mint_amount;
+        multisig.max_mint_amount = max_mint_amount;
+        emit!(MaxMintAmountChanged {
+            multisig: *multisig.to_account_info().key,
+            old_max_mint_amount: old,
+            max_mint_amount,
+        });
+        Ok(())
+    }
+
+    // Sets the voting weight of one of the owners. Until weights are set,
+    // every owner weighs one. If reweight_invalidates is set this bumps the
+    // owner set seqno, invalidating pending transactions approved under the
+    // old weights. The only way this can be invoked is via a recursive call
+    // from execute_transaction -> set_weight.
+    pub fn set_weight(ctx: Context<Auth>, owner: Pubkey, weight: u64) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let owner_index = multisig
+            .owners
+            .iter()
+            .position(|a| a == &owner)
+            .ok_or(ErrorCode::InvalidOwner)?;
+        if weight == 0 {
+            return Err(ErrorCode::InvalidWeight.into());
+        }
+
+        if multisig.weights.is_empty() {
+            multisig.weights = vec![1; multisig.owners.len()];
+        }
+        multisig.weights[owner_index] = weight;
+
+        let total_weight = multisig.total_weight();
+        if multisig.threshold > total_weight
+            || multisig.pause_threshold > total_weight
+            || multisig.unpause_threshold > total_weight
+        {
+            return Err(ErrorCode::InvalidThreshold.into());
+        }
+
+        if multisig.reweight_invalidates {
+            multisig.owner_set_seqno = multisig
+                .owner_set_seqno
+                .checked_add(1)
+                .ok_or(ErrorCode::Overflow)?;
+        }
+        snapshot_config(ctx.program_id, multisig, ctx.remaining_accounts)
+    }
+
+    // Chooses whether set_weight invalidates pending transactions. The only
+    // way this can be invoked is via a recursive call from execute_transaction
+    // -> set_reweight_invalidates.
+    pub fn set_reweight_invalidates(ctx: Context<Auth>, invalidates: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let old = multisig.reweight_invalidates;
+        multisig.reweight_invalidates = invalidates;
+        emit!(ReweightInvalidatesChanged {
+            multisig: *multisig.to_account_info().key,
+            old_invalidates: old,
+            invalidates,
+        });
+        Ok(())
+    }
+
+    // Chooses whether owners weigh the lamports they deposited into escrow,
+    // making the threshold a lamport amount, rather than their set weights.
+    // Enabling it fails if the contributions don't add up to the threshold.
+    // The only way this can be invoked is via a recursive call from
+    // execute_transaction -> set_weight_by_contribution.
+    pub fn set_weight_by_contribution(ctx: Context<Auth>, enabled: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let old = multisig.weight_by_contribution;
+        multisig.weight_by_contribution = enabled;
+        if multisig.threshold > multisig.total_weight() {
+            return Err(ErrorCode::InvalidThreshold.into());
+        }
+        emit!(WeightByContributionChanged {
+            multisig: *multisig.to_account_info().key,
+            old_enabled: old,
+            enabled,
+        });
+        Ok(())
+    }
+
+    // Chooses whether proposals with empty data or data that is a single
+    // repeated byte are rejected as likely client encoding bugs. The only way
+    // this can be invoked is via a recursive call from execute_transaction ->
+    // set_reject_trivial_data.
+    pub fn set_reject_trivial_data(ctx: Context<Auth>, enabled: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let old = multisig.reject_trivial_data;
+        multisig.reject_trivial_data = enabled;
+        emit!(RejectTrivialDataChanged {
+            multisig: *multisig.to_account_info().key,
+            old_enabled: old,
+            enabled,
+        });
+        Ok(())
+    }
+
+    // Replaces the names of all the owners at once, where names[index] is the
+    // name of owners[index]. The only way this can be invoked is via a
+    // recursive call from execute_transaction -> set_owner_names.
+    pub fn set_owner_names(ctx: Context<Auth>, names: Vec<String>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if names.len() != multisig.owners.len() {
+            return Err(ErrorCode::InvalidOwnerNames.into());
+        }
+        if names.iter().any(|name| name.len() > MAX_OWNER_NAME_LEN) {
+            return Err(ErrorCode::InvalidOwnerNames.into());
+        }
+        multisig.names = names;
+        emit!(OwnerNamesChanged {
+            multisig: *multisig.to_account_info().key,
+            names: multisig.names.clone(),
+        });
+        Ok(())
+    }
+
+    // Sets the human readable label of a transaction category tag, e.g.
+    // "Program Upgrade" for tag 2. An empty label clears it. The only way this
+    // can be invoked is via a recursive call from execute_transaction ->
+    // set_tag_label.
+    pub fn set_tag_label(ctx: Context<Auth>, tag: u8, label: String) -> Result<()> {
+        if label.len() > MAX_TAG_LABEL_LEN {
+            return Err(ErrorCode::TagLabelTooLong.into());
+        }
+        let tag_labels = &mut ctx.accounts.multisig.tag_labels;
+        let index = tag as usize;
+        if tag_labels.len() <= index {
+            tag_labels.resize(index + 1, String::new());
+        }
+        tag_labels[index] = label.clone();
+        emit!(TagLabelChanged {
+            multisig: *ctx.accounts.multisig.to_account_info().key,
+            tag,
+            label,
+        });
+        Ok(())
+    }
+
+    // Creates a vault, a signer of its own governed by the same owners, at the
+    // program address derived from [multisig, "vault", index] where index is
+    // the number of vaults created before. Each vault takes 5 bytes plus its
+    // label in the multisig account. The only way this can be invoked is via
+    // a recursive call from execute_transaction -> create_vault.
+    pub fn create_vault(ctx: Context<Auth>, label: String) -> Result<()> {
+        if label.len() > MAX_VAULT_LABEL_LEN {
+            return Err(ErrorCode::VaultLabelTooLong.into());
+        }
+        let multisig_key = *ctx.accounts.multisig.to_account_info().key;
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.vaults.len() > u8::MAX as usize {
+            return Err(ErrorCode::TooManyVaults.into());
+        }
+        let vault_index = multisig.vaults.len() as u8;
+        let (address, bump) = Pubkey::find_program_address(
+            &[
+                multisig_key.as_ref(),
+                VAULT_SEED,
+                &vault_index.to_le_bytes(),
+            ],
+            ctx.program_id,
+        );
+        multisig.vaults.push(Vault {
+            label: label.clone(),
+            bump,
+        });
+        msg!("Vault {} created: {}", vault_index, address);
+        emit!(VaultCreated {
+            multisig: multisig_key,
+            index: vault_index,
+            vault: address,
+            label,
+        });
+        Ok(())
+    }
+
+    // Chooses whether transactions adding owners, through add_owner or
+    // set_owners, need the approval of every current owner. The only way this
+    // can be invoked is via a recursive call from execute_transaction ->
+    // set_add_owner_requires_unanimous.
+    pub fn set_add_owner_requires_unanimous(ctx: Context<Auth>, enabled: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let old = multisig.add_owner_requires_unanimous;
+        multisig.add_owner_requires_unanimous = enabled;
+        emit!(AddOwnerRequiresUnanimousChanged {
+            multisig: *multisig.to_account_info().key,
+            old_enabled: old,
+            enabled,
+        });
+        Ok(())
+    }
+
+    // Assigns every owner to a group and requires approvals to span at least
+    // min_groups distinct groups, where owner_groups[index] is the group of
+    // owners[index]. A min_groups of zero turns the requirement off. The only
+    // way this can be invoked is via a recursive call from execute_transaction
+    // -> set_owner_groups.
+    pub fn set_owner_groups(
+        ctx: Context<Auth>,
+        owner_groups: Vec<u8>,
+        min_groups: u8,
+    ) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if min_groups > 0 && owner_groups.len() != multisig.owners.len() {
+            return Err(ErrorCode::InvalidOwnerGroups.into());
+        }
+        let mut groups = owner_groups.clone();
+        groups.sort_unstable();
+        groups.dedup();
+        if min_groups as usize > groups.len() {
+            return Err(ErrorCode::InvalidOwnerGroups.into());
+        }
+        emit!(OwnerGroupsChanged {
+            multisig: *multisig.to_account_info().key,
+            old_owner_groups: multisig.owner_groups.clone(),
+            old_min_groups: multisig.min_groups,
+            owner_groups: owner_groups.clone(),
+            min_groups,
+        });
+        multisig.owner_groups = owner_groups;
+        multisig.min_groups = min_groups;
+        Ok(())
+    }
+
+    // Sets how long transactions may stay pending before they expire, zero for
+    // never, and whether that is measured from their creation or from their
+    // first approval. The measure is fixed per transaction when it's created.
+    // The only way this can be invoked is via a recursive call from
+    // execute_transaction -> set_transaction_lifetime.
+    pub fn set_transaction_lifetime(
+        ctx: Context<Auth>,
+        lifetime: i64,
+        expiry_from: u8,
+    ) -> Result<()> {
+        if lifetime < 0 || expiry_from > EXPIRY_FROM_FIRST_APPROVAL {
+            return Err(ErrorCode::InvalidExpiry.into());
+        }
+        let multisig = &mut ctx.accounts.multisig;
+        emit!(TransactionLifetimeChanged {
+            multisig: *multisig.to_account_info().key,
+            old_lifetime: multisig.transaction_lifetime,
+            old_expiry_from: multisig.expiry_from,
+            lifetime,
+            expiry_from,
+        });
+        multisig.transaction_lifetime = lifetime;
+        multisig.expiry_from = expiry_from;
+        Ok(())
+    }
+
+    // Sets the age in seconds past which transactions can't be approved or
+    // executed, whatever their lifetime, zero for no limit. The only way this can be
+    // invoked is via a recursive call from execute_transaction ->
+    // set_max_proposal_age.
+    pub fn set_max_proposal_age(ctx: Context<Auth>, max_proposal_age: i64) -> Result<()> {
+        if max_proposal_age < 0 {
+            return Err(ErrorCode::InvalidExpiry.into());
+        }
+        let multisig = &mut ctx.accounts.multisig;
+        let old = multisig.max_proposal_age;
+        multisig.max_proposal_age = max_proposal_age;
+        emit!(MaxProposalAgeChanged {
+            multisig: *multisig.to_account_info().key,
+            old_max_proposal_age: old,
+            max_proposal_age,
+        });
+        Ok(())
+    }
+
+    // Proposes handing execution authority over to the given executor. The
+    // current executor, if any, stays in charge until the new one signs
+    // accept_executor, which it has to do within timeout seconds unless the
+    // timeout is zero. The only way this can be invoked is via a recursive
+    // call from execute_transaction -> propose_executor.
+    pub fn propose_executor(ctx: Context<Auth>, executor: Pubkey, timeout: i64) -> Result<()> {
+        if timeout < 0 {
+            return Err(ErrorCode::InvalidExpiry.into());
+        }
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.pending_executor = Some(executor);
+        multisig.pending_executor_deadline = if timeout == 0 {
+            0
+        } else {
+            Clock::get()?
+                .unix_timestamp
+                .checked_add(timeout)
+                .ok_or(ErrorCode::Overflow)?
+        };
+        emit!(ExecutorProposed {
+            multisig: *multisig.to_account_info().key,
+            executor,
+            deadline: multisig.pending_executor_deadline,
+        });
+        Ok(())
+    }
+
+    // Completes an executor handoff. Must be signed by the pending executor,
+    // so execution authority can't be handed to a key nobody controls.
+    pub fn accept_executor(ctx: Context<AcceptExecutor>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if multisig.pending_executor != Some(*ctx.accounts.executor.key) {
+            return Err(ErrorCode::InvalidPendingExecutor.into());
+        }
+        let deadline = multisig.pending_executor_deadline;
+        if deadline != 0 && Clock::get()?.unix_timestamp > deadline {
+            return Err(ErrorCode::ExecutorHandoffExpired.into());
+        }
+        multisig.executor = multisig.pending_executor.take();
+        multisig.pending_executor_deadline = 0;
+        emit!(ExecutorAccepted {
+            multisig: *multisig.to_account_info().key,
+            executor: *ctx.accounts.executor.key,
+        });
+        Ok(())
+    }
+
+    // Suspends or reinstates one of the owners. Approvals of suspended owners
+    // don't count until they are reinstated. The only way this can be invoked
+    // is via a recursive call from execute_transaction -> set_owner_suspended.
+    pub fn set_owner_suspended(ctx: Context<Auth>, owner: Pubkey, suspended: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if !multisig.owners.contains(&owner) {
+            return Err(ErrorCode::InvalidOwner.into());
+        }
+        multisig.suspended_owners.retain(|a| a != &owner);
+        if suspended {
+            multisig.suspended_owners.push(owner);
+            if multisig.active_weight() == 0 {
+                return Err(ErrorCode::NoActiveOwners.into());
+            }
+        }
+        emit!(OwnerSuspensionChanged {
+            multisig: *multisig.to_account_info().key,
+            owner,
+            suspended,
+        });
+        Ok(())
+    }
+
+    // Chooses whether the threshold required at execution is lowered to the
+    // weight of the active owners when suspensions leave it out of reach. The
+    // stored threshold is left as is. The only way this can be invoked is via
+    // a recursive call from execute_transaction -> set_clamp_threshold_to_active.
+    pub fn set_clamp_threshold_to_active(ctx: Context<Auth>, enabled: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let old = multisig.clamp_threshold_to_active;
+        multisig.clamp_threshold_to_active = enabled;
+        emit!(ClampThresholdToActiveChanged {
+            multisig: *multisig.to_account_info().key,
+            old_enabled: old,
+            enabled,
+        });
+        Ok(())
+    }
+
+    // Cancels one of the multisig's pending transactions so it can never be
+    // executed, e.g. one stuck behind a timelock. The only way this can be
+    // invoked is via a recursive call from execute_transaction ->
+    // cancel_transaction.
+    pub fn cancel_transaction(ctx: Context<CancelTransaction>) -> Result<()> {
+        let tx = &mut ctx.accounts.transaction;
+        if tx.did_execute || tx.status == TransactionStatus::Executed {
+            return Err(ErrorCode::AlreadyExecuted.into());
+        }
+        if tx.status == TransactionStatus::Cancelled {
+            return Err(ErrorCode::TransactionCancelled.into());
+        }
+        tx.status = TransactionStatus::Cancelled;
+        emit!(TransactionCancelled {
+            multisig: tx.multisig,
+            transaction: *tx.to_account_info().key,
+        });
+        Ok(())
+    }
+
+    // Proves the owners can still reach the threshold, by recording when the
+    // multisig last executed a heartbeat. The only way this can be invoked is
+    // via a recursive call from execute_transaction -> heartbeat.
+    pub fn heartbeat(ctx: Context<Auth>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.last_heartbeat_at = Clock::get()?.unix_timestamp;
+        emit!(Heartbeat {
+            multisig: *multisig.to_account_info().key,
+            timestamp: multisig.last_heartbeat_at,
+        });
+        Ok(())
+    }
+
+    // Sets the weight of rejections that blocks a transaction from executing,
+    // whatever its approvals, zero to never block. The only way this can be
+    // invoked is via a recursive call from execute_transaction ->
+    // set_reject_threshold.
+    pub fn set_reject_threshold(ctx: Context<Auth>, reject_threshold: u64) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        if reject_threshold > multisig.total_weight() {
+            return Err(ErrorCode::InvalidThreshold.into());
+        }
+        let old = multisig.reject_threshold;
+        multisig.reject_threshold = reject_threshold;
+        emit!(RejectThresholdChanged {
+            multisig: *multisig.to_account_info().key,
+            old_reject_threshold: old,
+            reject_threshold,
+        });
+        Ok(())
+    }
+
+    // Sets the escrowed lamports withdrawals must leave behind. The only way
+    // this can be invoked is via a recursive call from execute_transaction ->
+    // set_min_reserve.
+    pub fn set_min_reserve(ctx: Context<Auth>, min_reserve: u64) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let old = multisig.min_reserve;
+        multisig.min_reserve = min_reserve;
+        emit!(MinReserveChanged {
+            multisig: *multisig.to_account_info().key,
+            old_min_reserve: old,
+            min_reserve,
+        });
+        Ok(())
+    }
+
+    // Chooses whether only owners, or the designated executor, may execute
+    // transactions. The only way this can be invoked is via a recursive call
+    // from execute_transaction -> set_executor_must_be_owner.
+    pub fn set_executor_must_be_owner(ctx: Context<Auth>, enabled: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let old = multisig.executor_must_be_owner;
+        multisig.executor_must_be_owner = enabled;
+        emit!(ExecutorMustBeOwnerChanged {
+            multisig: *multisig.to_account_info().key,
+            old_enabled: old,
+            enabled,
+        });
+        Ok(())
+    }
+
+    // Chooses whether a TransactionApproved event is emitted for every
+    // approval, or a single ApprovalBatchSummary once the transaction is
+    // executed. The only way this can be invoked is via a recursive call from
+    // execute_transaction -> set_emit_per_approval.
+    pub fn set_emit_per_approval(ctx: Context<Auth>, enabled: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let old = multisig.emit_per_approval;
+        multisig.emit_per_approval = enabled;
+        emit!(EmitPerApprovalChanged {
+            multisig: *multisig.to_account_info().key,
+            old_enabled: old,
+            enabled,
+        });
+        Ok(())
+    }
+
+    // Chooses whether a transaction's proposer is barred from executing it.
+    // The only way this can be invoked is via a recursive call from
+    // execute_transaction -> set_proposer_cannot_execute.
+    pub fn set_proposer_cannot_execute(ctx: Context<Auth>, enabled: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let old = multisig.proposer_cannot_execute;
+        multisig.proposer_cannot_execute = enabled;
+        emit!(ProposerCannotExecuteChanged {
+            multisig: *multisig.to_account_info().key,
+            old_enabled: old,
+            enabled,
+        });
+        Ok(())
+    }
+
+    // Chooses whether transactions that would get write access to their
+    // executor's account are refused, so executing can't drain the executor.
+    // The only way this can be invoked is via a recursive call from
+    // execute_transaction -> set_protect_executor.
+    pub fn set_protect_executor(ctx: Context<Auth>, enabled: bool) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let old = multisig.protect_executor;
+        multisig.protect_executor = enabled;
+        emit!(ProtectExecutorChanged {
+            multisig: *multisig.to_account_info().key,
+            old_enabled: old,
+            enabled,
+        });
+        Ok(())
+    }
+
+    // Sets how many seconds a transaction flagged for review is held, zero
+    // to stop owners from flagging transactions. The only way this can be
+    // invoked is via a recursive call from execute_transaction ->
+    // set_review_period.
+    pub fn set_review_period(ctx: Context<Auth>, review_period: i64) -> Result<()> {
+        if review_period < 0 {
+            return Err(ErrorCode::InvalidExpiry.into());
+        }
+        let multisig = &mut ctx.accounts.multisig;
+        let old = multisig.review_period;
+        multisig.review_period = review_period;
+        emit!(ReviewPeriodChanged {
+            multisig: *multisig.to_account_info().key,
+            old_review_period: old,
+            review_period,
+        });
+        Ok(())
+    }
+
+    // TODO: Document
+    // Executes the given transaction if threshold owners have signed it. With
+    // profile set, the remaining compute units are logged around the CPI.
+    // Returns an ExecutionResult, which has executed unset when tampering
+    // only reset the approvals.
+    pub fn execute_transaction<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteTransaction<'info>>,
+        profile: bool,
+        vault_index: Option<u8>,
+    ) -> Result<()> {
+        // Has this been executed already?
+        if ctx.accounts.transaction.did_execute {
+            return Err(ErrorCode::AlreadyExecuted.into());
+        }
+
+        // Only pending transactions can be executed.
+        match ctx.accounts.transaction.status {
+            TransactionStatus::Pending => {}
+            TransactionStatus::Executed => return Err(ErrorCode::AlreadyExecuted.into()),
+            TransactionStatus::Cancelled => return Err(ErrorCode::TransactionCancelled.into()),
+            TransactionStatus::Expired => return Err(ErrorCode::TransactionExpired.into()),
+        }
+
+        // Frozen transactions must be unfrozen by the multisig first.
+        if ctx.accounts.transaction.frozen {
+            return Err(ErrorCode::TransactionFrozen.into());
+        }
+
+        // Flagged transactions wait out their review, however many approvals.
+        if Clock::get()?.unix_timestamp < ctx.accounts.transaction.review_hold_until {
+            return Err(ErrorCode::TransactionUnderReview.into());
+        }
+
+        // A paused multisig can only be unpaused (or paused again).
+        let pause_request = ctx.accounts.transaction.pause_request();
+        if ctx.accounts.multisig.paused && pause_request.is_none() {
+            return Err(ErrorCode::MultisigPaused.into());
+        }
+
+        // Approvals only hold for the content they were given for.
+        let content_hash = ctx.accounts.transaction.compute_content_hash();
+        if content_hash != ctx.accounts.transaction.content_hash {
+            if !ctx.accounts.multisig.invalidate_on_tamper {
+                return Err(ErrorCode::ContentHashMismatch.into());
+            }
+            msg!("Transaction content changed, approvals reset");
+            let tx = &mut ctx.accounts.transaction;
+            tx.signers = SignerSet::default();
+            tx.approved_at.iter_mut().for_each(|at| *at = 0);
+            tx.content_hash = content_hash;
+            tx.short_hash.copy_from_slice(&content_hash[..8]);
+            tx.tamper_seqno = tx.tamper_seqno.checked_add(1).ok_or(ErrorCode::Overflow)?;
+            let result = ExecutionResult {
+                executed: false,
+                signatures: 0,
+                threshold: required_threshold(&ctx.accounts.multisig, tx),
+            };
+            return return_execution_result(&result);
+        }
+
+        // Get the (weighted) count of valid signers on the pending transaction
+        let sig_count = ctx
+            .accounts
+            .multisig
+            .signer_weight(&ctx.accounts.transaction.signers);
+
+        // Has the transaction outlived the multisig's transaction lifetime?
+        let lifetime = ctx.accounts.multisig.transaction_lifetime;
+        if let Some(expires_at) = ctx.accounts.transaction.expires_at(lifetime) {
+            if Clock::get()?.unix_timestamp >= expires_at {
+                return Err(ErrorCode::TransactionExpired.into());
+            }
+        }
+
+        // Backstop against ancient transactions, independent of the lifetime.
+        check_proposal_age(&ctx.accounts.multisig, &ctx.accounts.transaction)?;
+
+        // Enough rejections veto the transaction, however many approvals.
+        if is_rejected(&ctx.accounts.multisig, &ctx.accounts.transaction) {
+            return Err(ErrorCode::TransactionRejected.into());
+        }
+
+        // Do we have enough signers on the transaction to execute?    
+        if sig_count < required_threshold(&ctx.accounts.multisig, &ctx.accounts.transaction) {
+            return Err(ErrorCode::NotEnoughSigners.into());
+        }
+
+        // Newcomers may have to be welcomed by everyone.
+        if ctx.accounts.multisig.add_owner_requires_unanimous
+            && ctx
+                .accounts
+                .transaction
+                .adds_owner(&ctx.accounts.multisig.owners)
+            && ctx.accounts.transaction.signers.count()
+                < ctx.accounts.multisig.owners.len() as u64
+        {
+            return Err(ErrorCode::UnanimityRequired.into());
+        }
+
+        // Approvals from a single group don't count as spread out, no matter
+        // how many owners of it signed.
+        let min_groups = ctx.accounts.multisig.min_groups as usize;
+        if min_groups > 0
+            && ctx
+                .accounts
+                .multisig
+                .group_spread(&ctx.accounts.transaction.signers)
+                < min_groups
+        {
+            return Err(ErrorCode::InsufficientGroupSpread.into());
+        }
+
+        // Only the designated executor may execute, if there is one.
+        if let Some(executor) = ctx.accounts.multisig.executor {
+            if ctx.accounts.executor.key != &executor {
+                return Err(ErrorCode::UnauthorizedExecutor.into());
+            }
+        }
+
+        // Keep outsiders from triggering executions, if so configured.
+        let multisig = &ctx.accounts.multisig;
+        let executor = ctx.accounts.executor.key;
+        if multisig.executor_must_be_owner
+            && !multisig.owners.contains(executor)
+            && multisig.executor != Some(*executor)
+        {
+            return Err(ErrorCode::InvalidOwner.into());
+        }
+
+        // Whoever executes shouldn't be putting their own account at stake.
+        if multisig.protect_executor
+            && Vec::<Instruction>::from(&*ctx.accounts.transaction)
+                .iter()
+                .flat_map(|ix| ix.accounts.iter())
+                .any(|acc| acc.is_writable && &acc.pubkey == executor)
+        {
+            return Err(ErrorCode::ExecutorAccountMutation.into());
+        }
+
+        // The proposer may have to leave execution to someone else.
+        if multisig.proposer_cannot_execute && executor == &ctx.accounts.transaction.proposer {
+            return Err(ErrorCode::ProposerCannotExecute.into());
+        }
+
+        // Four-eyes: the latest approver may not also be the executor until
+        // the cooldown has elapsed.
+        let tx = &ctx.accounts.transaction;
+        let cooldown = ctx.accounts.multisig.approve_execute_cooldown;
+        if cooldown > 0 && ctx.accounts.executor.key == &tx.last_approver {
+            let now = Clock::get()?.unix_timestamp;
+            if now < tx.last_approval_at.saturating_add(cooldown) {
+                return Err(ErrorCode::SameSignerApproveExecute.into());
+            }
+        }
+
+        // Give owners time to react once the threshold has been reached. A
+        // transaction that never recorded reaching it (e.g. the threshold was
+        // lowered afterwards) needs another approval to start the delay.
+        let min_slot_delay = ctx.accounts.multisig.min_slot_delay;
+        if min_slot_delay > 0 {
+            let ready_slot = tx.approved_slot.saturating_add(min_slot_delay);
+            if tx.approved_slot == 0 || Clock::get()?.slot < ready_slot {
+                return Err(ErrorCode::SlotDelayNotElapsed.into());
+            }
+        }
+
+        // Treasury timelock, counted in seconds from reaching the threshold.
+        // As with the slot delay, a transaction that never recorded reaching
+        // its threshold needs another approval.
+        if (ctx.accounts.multisig.timelock > 0 && tx.ready_at == 0)
+            || Clock::get()?.unix_timestamp < tx.ready_at
+        {
+            return Err(ErrorCode::TimelockNotElapsed.into());
+        }
+
+        // Pinned transactions only run against the deployment they were
+        // proposed for, whose program data account must be passed along.
+        if let Some(slot) = ctx.accounts.transaction.target_program_slot {
+            let pid = ctx.accounts.transaction.program_id;
+            let (address, _) = Pubkey::find_program_address(
+                &[pid.as_ref()],
+                &solana_program::bpf_loader_upgradeable::id(),
+            );
+            let program_data = ctx
+                .remaining_accounts
+                .iter()
+                .find(|info| info.key == &address)
+                .ok_or(ErrorCode::InvalidProgramData)?;
+            if program_deploy_slot(&pid, program_data)? != slot {
+                return Err(ErrorCode::TargetProgramChanged.into());
+            }
+        }
+
+        // Locked transactions only run against the accounts they were
+        // approved with.
+        if ctx.accounts.transaction.accounts_locked {
+            assert_accounts_locked(&ctx.accounts.transaction, ctx.remaining_accounts)?;
+        }
+
+        // Record who authorized the execution.
+        log_signers(&ctx.accounts.multisig, &ctx.accounts.transaction);
+
+        // Turn the transaction account into Instruction types, one per
+        // instruction of a batch.
+        let mut ixs: Vec<Instruction> = (&*ctx.accounts.transaction).into();
+
+        // The vault the proposal also signs with, if any.
+        let multisig_key = ctx.accounts.multisig.to_account_info().key;
+        let (vault_index, vault) = match vault_index {
+            Some(index) => (index, Some(ctx.accounts.multisig.vault(multisig_key, index)?)),
+            None => (0, None),
+        };
+
+        // Grab the metadata for what accounts should be passed to the instruction processor
+        // In this case we only want the multisig_signer Program Derived Address that we created with the programId and the multisig publicKey
+        escalate_signer(&mut ixs, ctx.accounts.multisig_signer.key);
+        if let Some((address, _)) = vault {
+            escalate_signer(&mut ixs, &address);
+        }
+
+        // Generate the seeds to find the multisig_signer Program Derived Address
+        let seeds = &[multisig_key.as_ref(), &[ctx.accounts.multisig.nonce]];
+
+        // The one signer needed to execute
+        let signer = &[&seeds[..]];
+
+        // Plus the vault's, when signing with one.
+        let vault_bump = vault.map_or(0, |(_, bump)| bump);
+        let vault_seeds = &[
+            multisig_key.as_ref(),
+            VAULT_SEED,
+            &vault_index.to_le_bytes(),
+            &[vault_bump],
+        ];
+        let mut signers = vec![&seeds[..]];
+        if vault.is_some() {
+            signers.push(&vault_seeds[..]);
+        }
+
+        // Grab all of the accounts that havent been touched
+        let accounts = ctx.remaining_accounts;
+
+        // Burn the transaction to ensure one time use, recording who executed
+        // it under which owner set and threshold. The account is written
+        // before any invocation, so a callee reentering execute_transaction
+        // finds it already executed.
+        ctx.accounts.transaction.did_execute = true;
+        ctx.accounts.transaction.status = TransactionStatus::Executed;
+        ctx.accounts.transaction.executor = *ctx.accounts.executor.key;
+        ctx.accounts.transaction.executed_owners = ctx.accounts.multisig.owners.clone();
+        ctx.accounts.transaction.executed_threshold =
+            required_threshold(&ctx.accounts.multisig, &ctx.accounts.transaction);
+        write_account(
+            &ctx.accounts.transaction.to_account_info(),
+            &*ctx.accounts.transaction,
+        )?;
+
+        // Durable transactions advance their nonce before anything else runs.
+        // The nonce account, the recent blockhashes sysvar and the system
+        // program must be passed along with the remaining accounts.
+        if let Some(nonce) = ctx.accounts.transaction.durable_nonce {
+            let advance_ix = solana_program::system_instruction::advance_nonce_account(
+                &nonce,
+                ctx.accounts.multisig_signer.key,
+            );
+            let mut nonce_accounts = accounts.to_vec();
+            nonce_accounts.push(ctx.accounts.multisig_signer.clone());
+            solana_program::program::invoke_signed(&advance_ix, &nonce_accounts, signer)?;
+        }
+
+        // Invoke a cross-program instruction with program signatures
+        if profile {
+            solana_program::log::sol_log_compute_units();
+        }
+        for ix in ixs.iter() {
+            solana_program::program::invoke_signed(ix, accounts, &signers)?;
+        }
+        if profile {
+            solana_program::log::sol_log_compute_units();
+        }
+
+        emit!(TransactionExecuted {
+            multisig: *ctx.accounts.multisig.to_account_info().key,
+            transaction: *ctx.accounts.transaction.to_account_info().key,
+            executor: *ctx.accounts.executor.key,
+        });
+        if !ctx.accounts.multisig.emit_per_approval {
+            emit_approval_summary(&ctx.accounts.multisig, &ctx.accounts.transaction);
+        }
+
+        // Tell callers composing with the multisig how the execution went.
+        let result = ExecutionResult {
+            executed: true,
+            signatures: ctx.accounts.transaction.approval_count(),
+            threshold: required_threshold(&ctx.accounts.multisig, &ctx.accounts.transaction),
+        };
+        return_execution_result(&result)
+    }
+}
+
+// ***** Helpers ***** //
+// Fills in a freshly created transaction account with the given proposal,
+// automatically signed by the proposer, who must be one of the owners.
+fn init_transaction<'info>(
+    multisig: &mut ProgramAccount<'info, Multisig>,
+    tx: &mut ProgramAccount<'info, Transaction>,
+    proposer: &Pubkey,
+    pid: Pubkey,
+    accs: Vec<TransactionAccount>,
+    data: Vec<u8>,
+) -> Result<()> {
+    let owner_index = multisig
+        .owners
+        .iter()
+        .position(|a| a == proposer)
+        .ok_or(ErrorCode::InvalidOwner)?;
+
+    check_proposal(
+        multisig,
+        multisig.to_account_info().key,
+        &tx.instructions,
+        &pid,
+        &accs,
+        &data,
+    )?;
+
+    // Make sure the proposal fits into the account the client allocated,
+    // otherwise serialization fails on exit with an opaque error. Batches
+    // have their instructions set beforehand.
+    let required = Transaction::space(multisig.owners.len(), accs.len(), data.len())
+        + tx.instructions.iter().map(TransactionInstruction::space).sum::<usize>();
+    let provided = tx.to_account_info().data_len();
+    if provided < required {
+        msg!(
+            "Transaction account requires {} bytes, got {}",
+            required,
+            provided
+        );
+        return Err(ErrorCode::AccountTooSmall.into());
+    }
+
+    let mut signers = SignerSet::default();
+    signers.set(owner_index);
+
+    tx.program_id = pid;
+    tx.accounts = accs;
+    tx.data = data;
+    tx.signers = signers;
+    tx.approved_at = vec![0; multisig.owners.len()];
+    tx.rejecters = vec![false; multisig.owners.len()];
+    tx.multisig = *multisig.to_account_info().key;
+    tx.did_execute = false;
+    tx.owner_set_seqno = multisig.owner_set_seqno;
+    tx.frozen = false;
+    tx.proposer = *proposer;
+    tx.last_approver = *proposer;
+    tx.last_approval_at = Clock::get()?.unix_timestamp;
+    tx.created_at = tx.last_approval_at;
+    tx.approved_at[owner_index] = tx.created_at;
+    tx.expiry_from = multisig.expiry_from;
+    tx.first_approved_at = 0;
+    tx.durable_nonce = None;
+    let content_hash = tx.compute_content_hash();
+    tx.content_hash = content_hash;
+    tx.short_hash.copy_from_slice(&content_hash[..8]);
+    tx.id = multisig.transaction_count;
+    tx.tamper_seqno = 0;
+    tx.bump = 0;
+    tx.approved_slot = 0;
+    tx.ready_at = 0;
+    tx.accounts_locked = false;
+    tx.executor = Pubkey::default();
+    tx.status = TransactionStatus::Pending;
+    tx.executed_owners = Vec::new();
+    tx.executed_threshold = 0;
+    tx.review_hold_until = 0;
+    note_threshold_reached(multisig, tx)?;
+
+    emit!(TransactionCreated {
+        multisig: *multisig.to_account_info().key,
+        transaction: *tx.to_account_info().key,
+        proposer: *proposer,
+        program_id: pid,
+        index: multisig.transaction_count,
+    });
+
+    multisig.transaction_count = multisig
+        .transaction_count
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+
+    Ok(())
+}
+
+// Checks the reference a withdrawal is made under against the multisig's
+// reference policy, and logs it.
+fn check_withdraw_reference(multisig: &Multisig, reference: &[u8; 32]) -> Result<()> {
+    if multisig.require_withdraw_reference && reference == &[0u8; 32] {
+        return Err(ErrorCode::MissingWithdrawReference.into());
+    }
+    if !multisig.withdraw_references.is_empty()
+        && !multisig.withdraw_references.contains(reference)
+    {
+        return Err(ErrorCode::UnauthorizedWithdrawReference.into());
+    }
+    msg!("reference: {}", base64::encode(reference));
+    Ok(())
+}
+
+// Accounts for lamports the given owner transferred into the multisig's
+// escrow, in its balance and in the owner's contribution.
+fn credit_deposit(multisig: &mut Multisig, depositor: &Pubkey, amount: u64) -> Result<()> {
+    let owner_index = multisig
+        .owners
+        .iter()
+        .position(|a| a == depositor)
+        .ok_or(ErrorCode::InvalidOwner)?;
+    multisig.lamports = multisig
+        .lamports
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    if multisig.contributions.is_empty() {
+        multisig.contributions = vec![0; multisig.owners.len()];
+    }
+    multisig.contributions[owner_index] = multisig.contributions[owner_index]
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// Counts an approval by the owner at the given index towards their limit for
+// the current window, failing once they've reached it. A new window starts
+// with the first approval after the previous one ended.
+fn throttle_approval(multisig: &mut Multisig, owner_index: usize) -> Result<()> {
+    if multisig.max_approvals_per_window == 0 {
+        return Ok(());
+    }
+    if multisig.approvals_this_window.len() != multisig.owners.len() {
+        multisig.approvals_this_window = vec![0; multisig.owners.len()];
+        multisig.window_starts = vec![0; multisig.owners.len()];
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let window_end = multisig.window_starts[owner_index].saturating_add(multisig.approval_window);
+    if now >= window_end {
+        multisig.window_starts[owner_index] = now;
+        multisig.approvals_this_window[owner_index] = 0;
+    }
+    if multisig.approvals_this_window[owner_index] >= multisig.max_approvals_per_window {
+        msg!(
+            "{}: approval rate exceeded, possibly compromised",
+            multisig.owners[owner_index]
+        );
+        return Err(ErrorCode::ApprovalRateExceeded.into());
+    }
+    multisig.approvals_this_window[owner_index] += 1;
+    Ok(())
+}
+
+// Counts a withdrawal of the given lamports towards the multisig's spending
+// limit for the current period, failing if it would go over. A new period
+// starts with the first withdrawal after the previous one ended.
+fn charge_spending(multisig: &mut Multisig, amount: u64) -> Result<()> {
+    if multisig.spending_limit == 0 {
+        return Ok(());
+    }
+    let now = Clock::get()?.unix_timestamp;
+    if now.saturating_sub(multisig.period_start) >= multisig.spending_period {
+        multisig.period_start = now;
+        multisig.spent_in_period = 0;
+    }
+    let spent = multisig
+        .spent_in_period
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    if spent > multisig.spending_limit {
+        return Err(ErrorCode::SpendingLimitExceeded.into());
+    }
+    multisig.spent_in_period = spent;
+    Ok(())
+}
+
+// Fails if the transaction is older than the multisig's maximum proposal age.
+fn check_proposal_age(multisig: &Multisig, tx: &Transaction) -> Result<()> {
+    if multisig.max_proposal_age > 0
+        && Clock::get()?.unix_timestamp.saturating_sub(tx.created_at) > multisig.max_proposal_age
+    {
+        return Err(ErrorCode::TransactionTooOld.into());
+    }
+    Ok(())
+}
+
+// Marks the transaction, whose address is given, as approved by the owner at
+// the given index.
+fn record_approval(
+    multisig: &Multisig,
+    tx: &mut Transaction,
+    transaction: &Pubkey,
+    owner_index: usize,
+) -> Result<()> {
+    check_proposal_age(multisig, tx)?;
+    tx.signers.set(owner_index);
+    tx.rejecters[owner_index] = false;
+    tx.last_approver = multisig.owners[owner_index];
+    tx.last_approval_at = Clock::get()?.unix_timestamp;
+    tx.approved_at[owner_index] = tx.last_approval_at;
+    if tx.first_approved_at == 0 {
+        tx.first_approved_at = tx.last_approval_at;
+    }
+    note_threshold_reached(multisig, tx)?;
+
+    if multisig.emit_per_approval {
+        emit!(TransactionApproved {
+            multisig: tx.multisig,
+            transaction: *transaction,
+            owner: tx.last_approver,
+            approval_weight: multisig.signer_weight(&tx.signers),
+            approvals: tx.approval_count(),
+            threshold: required_threshold(multisig, tx),
+        });
+    }
+    Ok(())
+}
+
+// True if the transaction's rejecters veto it, either by reaching the reject
+// threshold or by leaving too little weight for the threshold to be reached.
+// Like approvals, the weight left only counts owners who aren't suspended.
+fn is_rejected(multisig: &Multisig, tx: &Transaction) -> bool {
+    let rejected = multisig.approval_weight(&tx.rejecters);
+    (multisig.reject_threshold > 0 && rejected >= multisig.reject_threshold)
+        || (rejected > 0
+            && multisig.active_weight().saturating_sub(rejected) < required_threshold(multisig, tx))
+}
+
+// Emits an ApprovalRevoked for the owner withdrawing their approval. Unlike
+// approvals, revocations aren't left to the summary at execution, which
+// only lists the approvals that remain.
+fn emit_revocation(
+    multisig: &Multisig,
+    tx: &ProgramAccount<Transaction>,
+    owner: &Pubkey,
+    rejected: bool,
+) {
+    emit!(ApprovalRevoked {
+        multisig: tx.multisig,
+        transaction: *tx.to_account_info().key,
+        owner: *owner,
+        rejected,
+        approval_weight: multisig.signer_weight(&tx.signers),
+    });
+}
+
+// Emits the multisig's execution delays after one of them changed.
+fn emit_execution_delays(multisig: &ProgramAccount<Multisig>) {
+    emit!(ExecutionDelaysChanged {
+        multisig: *multisig.to_account_info().key,
+        min_slot_delay: multisig.min_slot_delay,
+        timelock: multisig.timelock,
+        approve_execute_cooldown: multisig.approve_execute_cooldown,
+    });
+}
+
+// Emits a LamportsDeposited for a deposit credited to the depositor.
+fn emit_deposit(multisig: &ProgramAccount<Multisig>, depositor: &Pubkey, amount: u64) {
+    emit!(LamportsDeposited {
+        multisig: *multisig.to_account_info().key,
+        depositor: *depositor,
+        amount,
+        escrow: multisig.lamports,
+    });
+}
+
+// Emits a single ApprovalBatchSummary of the transaction's approvals, in
+// place of the per approval events.
+fn emit_approval_summary(multisig: &ProgramAccount<Multisig>, tx: &ProgramAccount<Transaction>) {
+    let (signers, approved_at) = tx
+        .signers
+        .indices()
+        .filter(|index| *index < multisig.owners.len())
+        .map(|index| (multisig.owners[index], tx.approved_at[index]))
+        .unzip();
+    emit!(ApprovalBatchSummary {
+        multisig: *multisig.to_account_info().key,
+        transaction: *tx.to_account_info().key,
+        signers,
+        approved_at,
+    });
+}
+
+// Records the slot at which the transaction first reached its threshold, and
+// when its timelock runs out.
+fn note_threshold_reached(multisig: &Multisig, tx: &mut Transaction) -> Result<()> {
+    if tx.approved_slot == 0
+        && multisig.signer_weight(&tx.signers) >= required_threshold(multisig, tx)
+    {
+        let clock = Clock::get()?;
+        tx.approved_slot = clock.slot;
+        tx.ready_at = clock.unix_timestamp.saturating_add(multisig.timelock);
+    }
+    Ok(())
+}
+
+// Number of approvals needed to execute the given transaction. Pausing and
+// unpausing can be configured to require a different quorum than the rest,
+// and winding down is never clamped to the active owners.
+// With clamp_threshold_to_active it's capped at the active owners' weight,
+// but never below one so suspensions can't allow unapproved executions.
+// The transaction's threshold override, if any, applies on top.
+fn required_threshold(multisig: &Multisig, tx: &Transaction) -> u64 {
+    let threshold = match tx.pause_request() {
+        Some(true) if multisig.pause_threshold > 0 => multisig.pause_threshold,
+        Some(false) if multisig.unpause_threshold > 0 => multisig.unpause_threshold,
+        _ => multisig.threshold,
+    };
+    let threshold = if multisig.clamp_threshold_to_active
+        && !tx.calls("wind_down")
+        && !tx.calls("close_multisig")
+    {
+        threshold.min(multisig.active_weight().max(1))
+    } else {
+        threshold
+    };
+    threshold.max(tx.threshold_override.unwrap_or(0))
+}
+
+// Logs a single line with the index and key of every owner who signed the
+// transaction, the threshold and whether owners are weighted. Beyond
+// MAX_LOGGED_SIGNERS signers, only the number of remaining ones is logged.
+fn log_signers(multisig: &Multisig, tx: &Transaction) {
+    let signed: Vec<usize> = tx
+        .signers
+        .indices()
+        .filter(|index| *index < multisig.owners.len())
+        .collect();
+    let mut listed: Vec<String> = signed
+        .iter()
+        .take(MAX_LOGGED_SIGNERS)
+        .map(|index| format!("{}:{}", index, multisig.owners[*index]))
+        .collect();
+    if signed.len() > MAX_LOGGED_SIGNERS {
+        listed.push(format!("+{} more", signed.len() - MAX_LOGGED_SIGNERS));
+    }
+    msg!(
+        "signers: {} threshold: {} weighted: {}",
+        listed.join(","),
+        required_threshold(multisig, tx),
+        !multisig.weights.is_empty()
+    );
+}
+
+// Executes the transaction through a call to this program's
+// execute_transaction, so it goes through all of its checks. Both accounts
+// are written beforehand and read back afterwards, so what the execution
+// wrote isn't overwritten on exit.
+fn execute_via_cpi<'info>(
+    program_id: &Pubkey,
+    multisig: &mut ProgramAccount<'info, Multisig>,
+    multisig_signer: &AccountInfo<'info>,
+    transaction: &mut ProgramAccount<'info, Transaction>,
+    executor: &AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let multisig_info = multisig.to_account_info();
+    let transaction_info = transaction.to_account_info();
+    write_account(&multisig_info, &**multisig)?;
+    write_account(&transaction_info, &**transaction)?;
+
+    let mut metas = accounts::ExecuteTransaction {
+        multisig: *multisig_info.key,
+        multisig_signer: *multisig_signer.key,
+        transaction: *transaction_info.key,
+        executor: *executor.key,
+    }
+    .to_account_metas(None);
+    metas.extend(remaining_accounts.iter().map(|info| AccountMeta {
+        pubkey: *info.key,
+        is_signer: info.is_signer,
+        is_writable: info.is_writable,
+    }));
+    let ix = Instruction {
+        program_id: *program_id,
+        accounts: metas,
+        data: instruction::ExecuteTransaction {
+            profile: false,
+            vault_index: None,
+        }
+        .data(),
+    };
+    let mut infos = vec![
+        multisig_info.clone(),
+        multisig_signer.clone(),
+        transaction_info.clone(),
+        executor.clone(),
+    ];
+    infos.extend(remaining_accounts.iter().cloned());
+    solana_program::program::invoke(&ix, &infos)?;
+
+    **multisig = Multisig::try_deserialize(&mut &multisig_info.data.borrow()[..])?;
+    **transaction = Transaction::try_deserialize(&mut &transaction_info.data.borrow()[..])?;
+    Ok(())
+}
+
+// Unpacks the SPL token account, which must be held by the multisig signer
+// for the given mint.
+fn signer_token_account(
+    account: &AccountInfo,
+    multisig_signer: &Pubkey,
+    mint: &Pubkey,
+) -> Result<spl_token::state::Account> {
+    if account.owner != &spl_token::ID || account.data_len() != spl_token::state::Account::LEN {
+        return Err(ErrorCode::InvalidAuthorityAccount.into());
+    }
+    let token = spl_token::state::Account::unpack(&account.try_borrow_data()?)?;
+    if &token.owner != multisig_signer || &token.mint != mint {
+        return Err(ErrorCode::InvalidTokenAccount.into());
+    }
+    Ok(token)
+}
+
+// Checks a proposal against the rules every transaction has to follow,
+// failing with the first one it breaks. Batches are checked instruction by
+// instruction, other proposals by their pid, accs and data.
+fn check_proposal(
+    multisig: &Multisig,
+    multisig_key: &Pubkey,
+    instructions: &[TransactionInstruction],
+    pid: &Pubkey,
+    accs: &[TransactionAccount],
+    data: &[u8],
+) -> Result<()> {
+    let proposed: Vec<(&Pubkey, &[TransactionAccount], &[u8])> = if instructions.is_empty() {
+        vec![(pid, accs, data)]
+    } else {
+        instructions
+            .iter()
+            .map(|ix| (&ix.program_id, &ix.accounts[..], &ix.data[..]))
+            .collect()
+    };
+    // Lamports leave the multisig through its withdraw instructions only,
+    // where the escrow's limits apply.
+    let mut escrow = vec![*multisig_key];
+    escrow.extend(
+        Pubkey::create_program_address(&[multisig_key.as_ref(), &[multisig.nonce]], &crate::ID)
+            .ok(),
+    );
+    let is_trivial = |data: &[u8]| data.iter().all(|byte| Some(byte) == data.first());
+
+    for (program_id, accounts, data) in proposed {
+        if accounts.len() > MAX_TX_ACCOUNTS {
+            return Err(ErrorCode::TooManyAccounts.into());
+        }
+        if data.len() > MAX_TX_DATA_LEN {
+            return Err(ErrorCode::DataTooLarge.into());
+        }
+        if !multisig.allows_program(program_id) {
+            return Err(ErrorCode::ProgramNotAllowed.into());
+        }
+        if transfers_from(program_id, accounts, data, &escrow) {
+            return Err(ErrorCode::UseWithdrawInstruction.into());
+        }
+        if multisig.reject_trivial_data && is_trivial(data) {
+            return Err(ErrorCode::SuspiciousInstructionData.into());
+        }
+    }
+    Ok(())
+}
+
+// True if the instruction is a System Program transfer out of one of the
+// given accounts.
+fn transfers_from(
+    program_id: &Pubkey,
+    accounts: &[TransactionAccount],
+    data: &[u8],
+    sources: &[Pubkey],
+) -> bool {
+    // Bincode encodes the SystemInstruction variant as a u32 little endian.
+    const TRANSFER: u32 = 2;
+    const TRANSFER_WITH_SEED: u32 = 11;
+    if program_id != &solana_program::system_program::ID || data.len() < 4 {
+        return false;
+    }
+    let mut variant = [0u8; 4];
+    variant.copy_from_slice(&data[..4]);
+    // Transfers take the funding account first. Transfers with seed take its
+    // base, which signs for it, second.
+    let from = match u32::from_le_bytes(variant) {
+        TRANSFER => accounts.first(),
+        TRANSFER_WITH_SEED => accounts.get(1),
+        _ => None,
+    };
+    from.is_some_and(|from| sources.contains(&from.pubkey))
+}
+
+// Marks the given program address as a signer wherever the instructions pass
+// it, as the program signs for it when invoking them.
+fn escalate_signer(ixs: &mut [Instruction], signer: &Pubkey) {
+    for ix in ixs.iter_mut() {
+        for acc in ix.accounts.iter_mut() {
+            if &acc.pubkey == signer {
+                acc.is_signer = true;
+            }
+        }
+    }
+}
+
+// Returns the Borsh serialized result of an execution.
+fn return_execution_result(result: &ExecutionResult) -> Result<()> {
+    let data = result
+        .try_to_vec()
+        .map_err(|err| ProgramError::BorshIoError(err.to_string()))?;
+    set_return_data(&data);
+    Ok(())
+}
+
+// The 32 byte message an owner signs to approve a transaction off chain: the
+// SHA-256 of APPROVAL_MESSAGE_PREFIX, followed by the multisig's and the
+// transaction's addresses and the owner set seqno as a u32 little endian.
+pub fn approval_message(
+    multisig: &Pubkey,
+    transaction: &Pubkey,
+    owner_set_seqno: u32,
+) -> [u8; 32] {
+    solana_program::hash::hashv(&[
+        APPROVAL_MESSAGE_PREFIX,
+        multisig.as_ref(),
+        transaction.as_ref(),
+        &owner_set_seqno.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+// Sets the multisig's threshold, returning whether it took effect. Decreases
+// are only recorded while there's a threshold decrease delay, for
+// apply_threshold_change to apply once it has passed.
+fn set_threshold(multisig: &mut ProgramAccount<Multisig>, threshold: u64) -> Result<bool> {
+    if threshold < multisig.threshold && multisig.threshold_decrease_delay > 0 {
+        multisig.pending_threshold = threshold;
+        multisig.pending_threshold_ready_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(multisig.threshold_decrease_delay)
+            .ok_or(ErrorCode::Overflow)?;
+        msg!(
+            "Threshold decrease to {} pending until {}",
+            threshold,
+            multisig.pending_threshold_ready_at
+        );
+        emit!(ThresholdDecreaseScheduled {
+            multisig: *multisig.to_account_info().key,
+            threshold: multisig.threshold,
+            pending_threshold: threshold,
+            ready_at: multisig.pending_threshold_ready_at,
+        });
+        return Ok(false);
+    }
+    multisig.threshold = threshold;
+    multisig.pending_threshold = 0;
+    multisig.pending_threshold_ready_at = 0;
+    Ok(true)
+}
+
+// Splits total lamports equally among `owners` owners, the remainder going
+// to the first. Fails rather than hand out more or less than total.
+fn split_escrow(total: u64, owners: usize) -> Result<Vec<u64>> {
+    let count = owners as u64;
+    let share = total
+        .checked_div(count)
+        .ok_or(ErrorCode::OwnerListMismatch)?;
+    let remainder = total
+        .checked_rem(count)
+        .ok_or(ErrorCode::OwnerListMismatch)?;
+    let mut shares = vec![share; owners];
+    shares[0] = share.checked_add(remainder).ok_or(ErrorCode::Overflow)?;
+
+    let credited = shares
+        .iter()
+        .try_fold(0u64, |sum, share| sum.checked_add(*share))
+        .ok_or(ErrorCode::Overflow)?;
+    if credited != total {
+        return Err(ErrorCode::LamportsNotConserved.into());
+    }
+    Ok(shares)
+}
+
+// Emits the multisig's current config and appends it to the ConfigHistory
+// passed as the first remaining account, if any.
+fn snapshot_config(
+    program_id: &Pubkey,
+    multisig: &ProgramAccount<Multisig>,
+    remaining_accounts: &[AccountInfo],
+) -> Result<()> {
+    emit!(ConfigChanged {
+        multisig: *multisig.to_account_info().key,
+        owners: multisig.owners.clone(),
+        weights: multisig.weights.clone(),
+        threshold: multisig.threshold,
+        owner_set_seqno: multisig.owner_set_seqno,
+        description: multisig.description.clone(),
+    });
+
+    let info = match remaining_accounts.first() {
+        Some(info) => info,
+        None => return Ok(()),
+    };
+    if info.owner != program_id {
+        return Err(ErrorCode::InvalidConfigHistory.into());
+    }
+    let mut history = ConfigHistory::try_deserialize(&mut &info.data.borrow()[..])?;
+    if &history.multisig != multisig.to_account_info().key {
+        return Err(ErrorCode::InvalidConfigHistory.into());
+    }
+
+    let mut owners = Vec::with_capacity(multisig.owners.len() * 32);
+    for owner in multisig.owners.iter() {
+        owners.extend_from_slice(owner.as_ref());
+    }
+    history.entries.push(ConfigEntry {
+        seqno: history.entries.len() as u64 + 1,
+        owners_hash: solana_program::hash::hash(&owners).to_bytes(),
+        threshold: multisig.threshold,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    write_account(info, &history)
+}
+
+// Slot the upgradeable program `pid` was last deployed at, read from its
+// program data account.
+fn program_deploy_slot(pid: &Pubkey, program_data: &AccountInfo) -> Result<u64> {
+    let (address, _) = Pubkey::find_program_address(
+        &[pid.as_ref()],
+        &solana_program::bpf_loader_upgradeable::id(),
+    );
+    if program_data.key != &address {
+        return Err(ErrorCode::InvalidProgramData.into());
+    }
+    // Bincode encoded UpgradeableLoaderState::ProgramData: a u32 variant tag
+    // of 3 followed by the u64 slot.
+    let data = program_data.data.borrow();
+    if data.len() < 12 || data[..4] != 3u32.to_le_bytes() {
+        return Err(ErrorCode::InvalidProgramData.into());
+    }
+    let mut slot = [0u8; 8];
+    slot.copy_from_slice(&data[4..12]);
+    Ok(u64::from_le_bytes(slot))
+}
+
+// Serializes an account deserialized by hand back into its data.
+fn write_account<T: AccountSerialize>(info: &AccountInfo, account: &T) -> Result<()> {
+    let mut data = info.try_borrow_mut_data()?;
+    let dst: &mut [u8] = &mut data;
+    let mut cursor = std::io::Cursor::new(dst);
+    account.try_serialize(&mut cursor)?;
+    Ok(())
+}
+
+// True if the instruction data invokes the given instruction of this program.
+fn invokes(program_id: &Pubkey, data: &[u8], name: &str) -> bool {
+    program_id == &crate::ID && data.len() >= 8 && data[..8] == sighash(name)
+}
+
+// Anchor's 8 byte instruction discriminator for one of this program's
+// instructions.
+fn sighash(name: &str) -> [u8; 8] {
+    let preimage = format!("global:{}", name);
+    let mut sighash = [0u8; 8];
+    sighash.copy_from_slice(&solana_program::hash::hash(preimage.as_bytes()).to_bytes()[..8]);
+    sighash
+}
+
+// Like init_transaction, for a proposal built on chain by one of the typed
+// propose_* instructions.
+fn init_transaction_from_ix<'info>(
+    multisig: &mut ProgramAccount<'info, Multisig>,
+    tx: &mut ProgramAccount<'info, Transaction>,
+    proposer: &Pubkey,
+    ix: Instruction,
+) -> Result<()> {
+    let accs = ix.accounts.iter().map(TransactionAccount::from).collect();
+    init_transaction(multisig, tx, proposer, ix.program_id, accs, ix.data)
+}
+
+// Call to one of this program's governance instructions taking the Auth
+// accounts, with the given data.
+fn governance_ix(program_id: &Pubkey, accounts: &ProposeTransaction, data: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts::Auth {
+            multisig: *accounts.multisig.to_account_info().key,
+            multisig_signer: *accounts.multisig_signer.key,
+        }
+        .to_account_metas(None),
+        data,
+    }
+}
+
+// Checks that the account is an initialized durable nonce account whose
+// authority is the given key.
+fn assert_nonce_authority(nonce_account: &AccountInfo, authority: &Pubkey) -> Result<()> {
+    if nonce_account.owner != &solana_program::system_program::ID {
+        return Err(ErrorCode::InvalidNonceAccount.into());
+    }
+    // Layout: u32 version, u32 state (1 == initialized), authority pubkey, ...
+    let data = nonce_account.try_borrow_data()?;
+    if data.len() < 40 || data[4..8] != 1u32.to_le_bytes() || &data[8..40] != authority.as_ref() {
+        return Err(ErrorCode::InvalidNonceAccount.into());
+    }
+    Ok(())
+}
+
+// Checks that the accounts passed to execute a locked transaction are exactly
+// the transaction's accounts, in order and with the same writability,
+// followed by the program it invokes.
+fn assert_accounts_locked(tx: &Transaction, accounts: &[AccountInfo]) -> Result<()> {
+    if accounts.len() != tx.accounts.len() + 1 {
+        return Err(ErrorCode::AccountSubstitutionDetected.into());
+    }
+    for (info, account) in accounts.iter().zip(tx.accounts.iter()) {
+        if info.key != &account.pubkey || info.is_writable != account.is_writable {
+            return Err(ErrorCode::AccountSubstitutionDetected.into());
+        }
+    }
+    if accounts[tx.accounts.len()].key != &tx.program_id {
+        return Err(ErrorCode::AccountSubstitutionDetected.into());
+    }
+    Ok(())
+}
+
+// Hands the authority over each of the given SPL mints (minting) and token
+// accounts (ownership) from the current authority to the new one.
+fn repoint_authorities<'info>(
+    accounts: &[AccountInfo<'info>],
+    authority: &AccountInfo<'info>,
+    new_authority: &Pubkey,
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    for account in accounts.iter() {
+        let authority_type = match account.data_len() {
+            spl_token::state::Mint::LEN => spl_token::instruction::AuthorityType::MintTokens,
+            spl_token::state::Account::LEN => spl_token::instruction::AuthorityType::AccountOwner,
+            _ => return Err(ErrorCode::InvalidAuthorityAccount.into()),
+        };
+        if account.owner != &spl_token::ID {
+            return Err(ErrorCode::InvalidAuthorityAccount.into());
+        }
+        let ix = spl_token::instruction::set_authority(
+            &spl_token::ID,
+            account.key,
+            Some(new_authority),
+            authority_type,
+            authority.key,
+            &[],
+        )?;
+        solana_program::program::invoke(
+            &ix,
+            &[account.clone(), authority.clone(), token_program.clone()],
+        )?;
+        msg!("{}: authority moved to {}", account.key, new_authority);
+    }
+    Ok(())
+}
+
+// Checks that there's one non-zero weight per owner, if any. Weights that are
+// all one are dropped, as that's what no weights mean.
+fn owner_weights(owners: &[Pubkey], weights: Vec<u64>) -> Result<Vec<u64>> {
+    if weights.is_empty() {
+        return Ok(weights);
+    }
+    if weights.len() != owners.len() {
+        return Err(ErrorCode::WeightsMismatch.into());
+    }
+    if weights.contains(&0) {
+        return Err(ErrorCode::InvalidWeight.into());
+    }
+    if weights.iter().all(|weight| *weight == 1) {
+        return Ok(Vec::new());
+    }
+    Ok(weights)
+}
+
+// Sum of the given weights of the owners, the owner count if there are none.
+fn sum_weights(owners: &[Pubkey], weights: &[u64]) -> Result<u64> {
+    if weights.is_empty() {
+        return Ok(owners.len() as u64);
+    }
+    weights
+        .iter()
+        .try_fold(0u64, |total, weight| total.checked_add(*weight))
+        .ok_or_else(|| ErrorCode::Overflow.into())
+}
+
+// Checks that there's at least one owner, at most MAX_OWNERS, and no owner is
+// listed twice, as the signers bitmap assumes each owner has a single index.
+fn assert_unique_owners(owners: &[Pubkey]) -> Result<()> {
+    if owners.is_empty() {
+        return Err(ErrorCode::InvalidOwner.into());
+    }
+    if owners.len() > MAX_OWNERS {
+        return Err(ErrorCode::TooManyOwners.into());
+    }
+    for (index, owner) in owners.iter().enumerate() {
+        if owners[..index].contains(owner) {
+            return Err(ErrorCode::DuplicateOwner.into());
+        }
+    }
+    Ok(())
+}
 
 // ***** Contexts ***** //
 #[derive(Accounts)]
-pub struct CreateMultisig<'info> {
-    #[account(zero)]
+pub struct CreateMultisig<'info> {
+    #[account(zero)]
+    multisig: ProgramAccount<'info, Multisig>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CreateConfigHistory<'info> {
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(zero)]
+    history: ProgramAccount<'info, ConfigHistory>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CreateTransaction<'info> {
+    #[account(mut)]
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(zero)]
+    transaction: ProgramAccount<'info, Transaction>,
+    // One of the owners. Checked in the handler.
+    #[account(signer)]
+    proposer: AccountInfo<'info>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(pid: Pubkey, accs: Vec<TransactionAccount>, data: Vec<u8>, index: u64, bump: u8)]
+pub struct CreateTransactionPda<'info> {
+    #[account(mut)]
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(
+        init,
+        seeds = [
+            b"tx".as_ref(),
+            multisig.to_account_info().key.as_ref(),
+            &index.to_le_bytes(),
+        ],
+        bump = bump,
+        payer = proposer,
+        space = Transaction::space(multisig.owners.len(), accs.len(), data.len()),
+    )]
+    transaction: ProgramAccount<'info, Transaction>,
+    // One of the owners, paying for the transaction account. Checked in the
+    // handler.
+    #[account(mut, signer)]
+    proposer: AccountInfo<'info>,
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CreateDurableTransaction<'info> {
+    #[account(mut)]
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(
+        seeds = [multisig.to_account_info().key.as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: AccountInfo<'info>,
+    #[account(zero)]
+    transaction: ProgramAccount<'info, Transaction>,
+    // One of the owners. Checked in the handler.
+    #[account(signer)]
+    proposer: AccountInfo<'info>,
+    // Durable nonce account whose authority is the multisig signer. Checked
+    // in the handler.
+    nonce_account: AccountInfo<'info>,
+    rent: Sysvar<'info, Rent>,
+}
+
+// Accounts for the typed propose_* instructions, which build the wrapped
+// instruction on chain around the multisig signer.
+#[derive(Accounts)]
+pub struct ProposeTransaction<'info> {
+    #[account(mut)]
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(
+        seeds = [multisig.to_account_info().key.as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: AccountInfo<'info>,
+    #[account(zero)]
+    transaction: ProgramAccount<'info, Transaction>,
+    // One of the owners. Checked in the handler.
+    #[account(signer)]
+    proposer: AccountInfo<'info>,
+    rent: Sysvar<'info, Rent>,
+}
+
+// The (multisig, transaction) pairs are passed as remaining accounts.
+#[derive(Accounts)]
+pub struct ApproveMulti<'info> {
+    // Owner of some of the multisigs. Checked in the handler.
+    #[account(signer)]
+    owner: AccountInfo<'info>,
+}
+
+// Proposing, approving and executing a transaction at once. The co-signing
+// owners, the wrapped instruction's accounts and its program are passed as
+// remaining accounts, followed by this program.
+#[derive(Accounts)]
+pub struct ProposeApproveExecute<'info> {
+    #[account(mut)]
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(
+        seeds = [multisig.to_account_info().key.as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: AccountInfo<'info>,
+    #[account(zero)]
+    transaction: ProgramAccount<'info, Transaction>,
+    // One of the owners, executing as well. Checked in the handler.
+    #[account(signer)]
+    proposer: AccountInfo<'info>,
+    rent: Sysvar<'info, Rent>,
+}
+
+// Approving a transaction and executing it once approved. The wrapped
+// instruction's accounts and its program are passed as remaining accounts,
+// followed by this program.
+#[derive(Accounts)]
+pub struct ApproveAndExecute<'info> {
+    #[account(
+        mut,
+        constraint = multisig.owner_set_seqno == transaction.owner_set_seqno
+            @ ErrorCode::OwnerSetChanged,
+    )]
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(
+        seeds = [multisig.to_account_info().key.as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: AccountInfo<'info>,
+    #[account(mut, has_one = multisig)]
+    transaction: ProgramAccount<'info, Transaction>,
+    // One of the owners, executing as well. Checked in the handler.
+    #[account(signer)]
+    owner: AccountInfo<'info>,
+}
+
+// Deleting a transaction, whose rent goes back to the proposer.
+#[derive(Accounts)]
+pub struct DeleteTransaction<'info> {
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(mut, has_one = multisig, close = proposer)]
+    transaction: ProgramAccount<'info, Transaction>,
+    // The transaction's proposer. Checked in the handler.
+    #[account(mut, signer)]
+    proposer: AccountInfo<'info>,
+}
+
+// TODO: Document
+#[derive(Accounts)]
+pub struct Approve<'info> {
+    #[account(
+        mut,
+        constraint = multisig.owner_set_seqno == transaction.owner_set_seqno
+            @ ErrorCode::OwnerSetChanged,
+    )]
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(mut, has_one = multisig)]
+    transaction: ProgramAccount<'info, Transaction>,
+    // One of the multisig owners. Checked in the handler.
+    #[account(signer)]
+    owner: AccountInfo<'info>,
+}
+
+// Importing the approvals of a sibling transaction.
+#[derive(Accounts)]
+pub struct ImportApprovals<'info> {
+    #[account(
+        constraint = multisig.owner_set_seqno == transaction.owner_set_seqno
+            @ ErrorCode::OwnerSetChanged,
+    )]
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(mut, has_one = multisig)]
+    transaction: ProgramAccount<'info, Transaction>,
+    #[account(mut, has_one = multisig)]
+    source: ProgramAccount<'info, Transaction>,
+    // One of the multisig owners. Checked in the handler.
+    #[account(signer)]
+    owner: AccountInfo<'info>,
+}
+
+// Depositing lamports into the multisig's escrow.
+#[derive(Accounts)]
+pub struct Escrow<'info> {
+    #[account(mut)]
+    multisig: ProgramAccount<'info, Multisig>,
+    // One of the multisig owners, funding the deposit. Checked in the handler.
+    #[account(mut, signer)]
+    owner: AccountInfo<'info>,
+    system_program: Program<'info, System>,
+}
+
+// Depositing tokens into the multisig signer's token account.
+#[derive(Accounts)]
+pub struct DepositToken<'info> {
     multisig: ProgramAccount<'info, Multisig>,
-    rent: Sysvar<'info, Rent>,
+    #[account(
+        seeds = [multisig.to_account_info().key.as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: AccountInfo<'info>,
+    // Checked in the handler.
+    mint: AccountInfo<'info>,
+    // The owner's token account the tokens come from.
+    #[account(mut)]
+    source: AccountInfo<'info>,
+    // The multisig signer's token account of the mint. Checked in the
+    // handler.
+    #[account(mut)]
+    vault: AccountInfo<'info>,
+    // One of the multisig owners, authority over the source. Checked in the
+    // handler.
+    #[account(signer)]
+    owner: AccountInfo<'info>,
+    #[account(constraint = token_program.key == &spl_token::ID)]
+    token_program: AccountInfo<'info>,
 }
 
+// TODO: Document
 #[derive(Accounts)]
-pub struct CreateTransaction<'info> {
+pub struct Auth<'info> {
+    #[account(mut)]
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(
+        signer,
+        seeds = [multisig.to_account_info().key.as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: AccountInfo<'info>,
+}
+
+// Like Auth, but moving the multisig over to a new signer. The mints and
+// token accounts whose authority moves along are passed as remaining
+// accounts.
+#[derive(Accounts)]
+pub struct RotateSigner<'info> {
+    #[account(mut)]
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(
+        signer,
+        seeds = [multisig.to_account_info().key.as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: AccountInfo<'info>,
+    // The signer derived with the new nonce. Checked in the handler.
+    new_multisig_signer: AccountInfo<'info>,
+    #[account(constraint = token_program.key == &spl_token::ID)]
+    token_program: AccountInfo<'info>,
+}
+
+// Like Auth, but settling another of the multisig's transactions.
+#[derive(Accounts)]
+pub struct CancelTransaction<'info> {
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(
+        signer,
+        seeds = [multisig.to_account_info().key.as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: AccountInfo<'info>,
+    #[account(mut, has_one = multisig)]
+    transaction: ProgramAccount<'info, Transaction>,
+}
+
+// Like Auth, but moving tokens out of one of the multisig signer's token
+// accounts.
+#[derive(Accounts)]
+pub struct RecoverToken<'info> {
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(
+        signer,
+        seeds = [multisig.to_account_info().key.as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: AccountInfo<'info>,
+    // Checked in the handler.
+    #[account(mut)]
+    token_account: AccountInfo<'info>,
+    #[account(mut)]
+    destination: AccountInfo<'info>,
+    #[account(constraint = token_program.key == &spl_token::ID)]
+    token_program: AccountInfo<'info>,
+}
+
+// Like Auth, but withdrawing tokens from the multisig signer's token
+// account of the mint.
+#[derive(Accounts)]
+pub struct WithdrawToken<'info> {
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(
+        signer,
+        seeds = [multisig.to_account_info().key.as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: AccountInfo<'info>,
+    // Checked in the handler.
+    mint: AccountInfo<'info>,
+    // Checked in the handler.
+    #[account(mut)]
+    vault: AccountInfo<'info>,
+    #[account(mut)]
+    destination: AccountInfo<'info>,
+    #[account(constraint = token_program.key == &spl_token::ID)]
+    token_program: AccountInfo<'info>,
+}
+
+// Depositing into escrow while proposing a withdrawal.
+#[derive(Accounts)]
+pub struct DepositAndProposeWithdrawal<'info> {
+    #[account(mut)]
     multisig: ProgramAccount<'info, Multisig>,
+    #[account(
+        seeds = [multisig.to_account_info().key.as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: AccountInfo<'info>,
     #[account(zero)]
     transaction: ProgramAccount<'info, Transaction>,
     // One of the owners. Checked in the handler.
-    #[account(signer)]
-    proposer: AccountInfo<'info>,
+    #[account(mut, signer)]
+    depositor: AccountInfo<'info>,
+    system_program: Program<'info, System>,
     rent: Sysvar<'info, Rent>,
 }
 
-// TODO: Document
+// Like Auth, but paying the escrow out to the owners, who are passed as
+// remaining accounts.
 #[derive(Accounts)]
-pub struct Approve<'info> {
-    #[account(constraint = multisig.owner_set_seqno == transaction.owner_set_seqno)]
+pub struct DistributeEscrow<'info> {
+    #[account(mut)]
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(
+        signer,
+        seeds = [multisig.to_account_info().key.as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: AccountInfo<'info>,
+}
+
+// Initiating or cancelling an emergency withdrawal.
+#[derive(Accounts)]
+pub struct Emergency<'info> {
+    #[account(mut)]
     multisig: ProgramAccount<'info, Multisig>,
-    #[account(mut, has_one = multisig)]
-    transaction: ProgramAccount<'info, Transaction>,
     // One of the multisig owners. Checked in the handler.
     #[account(signer)]
     owner: AccountInfo<'info>,
 }
 
-// TODO: Document
+// Completing an emergency withdrawal to the recipient set at creation.
 #[derive(Accounts)]
-pub struct Escrow<'info> {
-    #[account(constraint = multisig.owner_set_seqno == transaction.owner_set_seqno)]
+pub struct EmergencyWithdraw<'info> {
+    #[account(mut)]
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(mut, constraint = emergency_recipient.key == &multisig.emergency_recipient)]
+    emergency_recipient: AccountInfo<'info>,
+}
+
+// Like Auth, but paying escrowed lamports out to the destination.
+#[derive(Accounts)]
+pub struct WithdrawEscrow<'info> {
+    #[account(mut)]
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(
+        signer,
+        seeds = [multisig.to_account_info().key.as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: AccountInfo<'info>,
+    #[account(mut)]
+    destination: AccountInfo<'info>,
+}
+
+// Like WithdrawEscrow, but closing the multisig into the destination.
+#[derive(Accounts)]
+pub struct WindDown<'info> {
+    #[account(mut, close = destination)]
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(
+        signer,
+        seeds = [multisig.to_account_info().key.as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: AccountInfo<'info>,
+    #[account(mut)]
+    destination: AccountInfo<'info>,
+}
+
+// Like WithdrawEscrow, but paying into another multisig's escrow.
+#[derive(Accounts)]
+pub struct TransferEscrow<'info> {
+    #[account(mut)]
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(
+        signer,
+        seeds = [multisig.to_account_info().key.as_ref()],
+        bump = multisig.nonce,
+    )]
+    multisig_signer: AccountInfo<'info>,
+    #[account(
+        mut,
+        constraint = destination.to_account_info().key != multisig.to_account_info().key,
+    )]
+    destination: ProgramAccount<'info, Multisig>,
+}
+
+// Actions any single owner can take on the multisig.
+#[derive(Accounts)]
+pub struct OwnerAction<'info> {
+    #[account(mut)]
     multisig: ProgramAccount<'info, Multisig>,
-    #[account(mut, has_one = multisig)]
-    transaction: ProgramAccount<'info, Transaction>,
     // One of the multisig owners. Checked in the handler.
     #[account(signer)]
     owner: AccountInfo<'info>,
 }
 
-// TODO: Document
+// Like Auth, but for governance actions that operate on one of the
+// multisig's transactions rather than on the multisig itself.
 #[derive(Accounts)]
-pub struct Auth<'info> {
-    #[account(mut)]
+pub struct TransactionAuth<'info> {
     multisig: ProgramAccount<'info, Multisig>,
     #[account(
         signer,
@@ -268,12 +4272,71 @@ pub struct Auth<'info> {
         bump = multisig.nonce,
     )]
     multisig_signer: AccountInfo<'info>,
+    #[account(mut, has_one = multisig)]
+    transaction: ProgramAccount<'info, Transaction>,
+}
+
+// Read-only access to the multisig, on behalf of a would-be proposer.
+#[derive(Accounts)]
+pub struct ValidateProposal<'info> {
+    multisig: ProgramAccount<'info, Multisig>,
+    // Checked in the handler.
+    proposer: AccountInfo<'info>,
+}
+
+// Read-only access to one of the multisig's transactions.
+#[derive(Accounts)]
+pub struct InspectTransaction<'info> {
+    multisig: ProgramAccount<'info, Multisig>,
+    #[account(has_one = multisig)]
+    transaction: ProgramAccount<'info, Transaction>,
+}
+
+// Accepting an executor handoff.
+#[derive(Accounts)]
+pub struct AcceptExecutor<'info> {
+    #[account(mut)]
+    multisig: ProgramAccount<'info, Multisig>,
+    // The pending executor. Checked in the handler.
+    #[account(signer)]
+    executor: AccountInfo<'info>,
+}
+
+// The transactions to prune are passed as remaining accounts.
+#[derive(Accounts)]
+pub struct PruneExecuted<'info> {
+    multisig: ProgramAccount<'info, Multisig>,
+    // One of the multisig owners. Checked in the handler.
+    #[account(signer)]
+    owner: AccountInfo<'info>,
+    // Receives the pruned transactions' rent.
+    #[account(mut)]
+    collector: AccountInfo<'info>,
+}
+
+// The transactions to count are passed as remaining accounts.
+#[derive(Accounts)]
+pub struct ProposalStats<'info> {
+    multisig: ProgramAccount<'info, Multisig>,
+}
+
+// Auditing the multisig's owners.
+#[derive(Accounts)]
+pub struct AuditOwners<'info> {
+    multisig: ProgramAccount<'info, Multisig>,
 }
 
+// The multisigs to check are passed as remaining accounts.
+#[derive(Accounts)]
+pub struct IsOwnerMulti {}
+
 // TODO: Document
 #[derive(Accounts)]
 pub struct ExecuteTransaction<'info> {
-    #[account(constraint = multisig.owner_set_seqno == transaction.owner_set_seqno)]
+    #[account(
+        constraint = multisig.owner_set_seqno == transaction.owner_set_seqno
+            @ ErrorCode::OwnerSetChanged,
+    )]
     multisig: ProgramAccount<'info, Multisig>,
     #[account(
         seeds = [multisig.to_account_info().key.as_ref()],
@@ -282,6 +4345,9 @@ pub struct ExecuteTransaction<'info> {
     multisig_signer: AccountInfo<'info>,
     #[account(mut, has_one = multisig)]
     transaction: ProgramAccount<'info, Transaction>,
+    // Whoever submits the execution.
+    #[account(signer)]
+    executor: AccountInfo<'info>,
 }
 
 // ***** Data Accounts ***** //
@@ -294,6 +4360,296 @@ pub struct Multisig {
     pub nonce: u8,
     pub owner_set_seqno: u32,
     pub lamports: u64,
+    // Seconds during which the latest approver can't execute a transaction.
+    pub approve_execute_cooldown: i64,
+    // Emergency pause, blocking execution of everything but set_pause.
+    pub paused: bool,
+    // Approvals needed to pause and unpause; zero means `threshold`.
+    pub pause_threshold: u64,
+    pub unpause_threshold: u64,
+    // Reset approvals instead of failing when a transaction's content hash
+    // doesn't match at execution.
+    pub invalidate_on_tamper: bool,
+    // While set, the threshold may only be raised.
+    pub alert_mode: bool,
+    // Number of transactions created so far, used to index transaction PDAs.
+    pub transaction_count: u64,
+    // Slots that must pass between reaching the threshold and execution.
+    pub min_slot_delay: u64,
+    // Most a single propose_mint_to proposal may mint, zero for no cap.
+    pub max_mint_amount: u64,
+    // weights[index] is the voting weight of owners[index]. Empty means every
+    // owner weighs one.
+    pub weights: Vec<u64>,
+    // Whether set_weight invalidates pending transactions.
+    pub reweight_invalidates: bool,
+    // owner_groups[index] is the group of owners[index].
+    pub owner_groups: Vec<u8>,
+    // Distinct groups the approvals must span, zero for no requirement.
+    pub min_groups: u8,
+    // Seconds a transaction may stay pending before it expires, zero for no
+    // expiry.
+    pub transaction_lifetime: i64,
+    // The expiry_from new transactions are created with.
+    pub expiry_from: u8,
+    // The only key allowed to execute transactions, anyone if None.
+    pub executor: Option<Pubkey>,
+    // Executor proposed to take over, until it signs accept_executor.
+    pub pending_executor: Option<Pubkey>,
+    // Unix timestamp after which the pending executor can no longer accept,
+    // zero for no deadline.
+    pub pending_executor_deadline: i64,
+    // Owners whose approvals don't count for the time being.
+    pub suspended_owners: Vec<Pubkey>,
+    // Whether the required threshold is capped at the active owners' weight.
+    pub clamp_threshold_to_active: bool,
+    // Escrowed lamports withdrawals must leave behind.
+    pub min_reserve: u64,
+    // Whether only owners (or the executor) may execute transactions.
+    pub executor_must_be_owner: bool,
+    // Whether every approval emits an event, rather than a single summary at
+    // execution.
+    pub emit_per_approval: bool,
+    // Whether a transaction's proposer is barred from executing it.
+    pub proposer_cannot_execute: bool,
+    // contributions[index] is the lamports owners[index] deposited into
+    // escrow. Empty means nobody deposited yet.
+    pub contributions: Vec<u64>,
+    // Whether owners weigh their contributions instead of their weights.
+    pub weight_by_contribution: bool,
+    // Whether proposals with empty or single repeated byte data are rejected.
+    pub reject_trivial_data: bool,
+    // tag_labels[tag] is the label of transactions tagged tag, empty if unset.
+    pub tag_labels: Vec<String>,
+    // Whether adding owners needs the approval of every current owner.
+    pub add_owner_requires_unanimous: bool,
+    // Unix timestamp of the latest heartbeat, zero if there hasn't been one.
+    pub last_heartbeat_at: i64,
+    // Whether transactions writing to their executor's account are refused.
+    pub protect_executor: bool,
+    // names[index] is the name of owners[index]. Empty until names are set.
+    pub names: Vec<String>,
+    // Whether withdrawals must be made under a non-zero reference.
+    pub require_withdraw_reference: bool,
+    // The only references withdrawals may be made under, any if empty.
+    pub withdraw_references: Vec<[u8; 32]>,
+    // Where emergency withdrawals send the escrow, none if the default key.
+    pub emergency_recipient: Pubkey,
+    // Approval weight needed to initiate an emergency withdrawal.
+    pub emergency_threshold: u64,
+    // Seconds an initiated emergency withdrawal waits before completing.
+    pub emergency_delay: i64,
+    // emergency_approvals[index] is true if owners[index] approved the
+    // pending emergency withdrawal.
+    pub emergency_approvals: Vec<bool>,
+    // Unix timestamp the emergency withdrawal was initiated at, zero if none.
+    pub emergency_initiated_at: i64,
+    // Weight of rejections that blocks a transaction, zero if they never do.
+    pub reject_threshold: u64,
+    // Seconds between a transaction reaching its threshold and becoming
+    // executable.
+    pub timelock: i64,
+    // Seconds after their creation past which transactions can't be
+    // executed, zero for no limit.
+    pub max_proposal_age: i64,
+    // Owner no owner change may remove, if any.
+    pub permanent_owner: Option<Pubkey>,
+    // Most approvals an owner may give per approval window, zero for no limit.
+    pub max_approvals_per_window: u64,
+    // Length of an owner's approval window, in seconds.
+    pub approval_window: i64,
+    // approvals_this_window[index] is the number of approvals owners[index]
+    // gave in their current window, which started at window_starts[index].
+    // Empty until the first throttled approval.
+    pub approvals_this_window: Vec<u64>,
+    pub window_starts: Vec<i64>,
+    // vaults[index] is the vault created with the given index.
+    pub vaults: Vec<Vault>,
+    // Seconds a threshold decrease waits before it can be applied.
+    pub threshold_decrease_delay: i64,
+    // Threshold decrease applicable from pending_threshold_ready_at, a unix
+    // timestamp that is zero when no decrease is pending.
+    pub pending_threshold: u64,
+    pub pending_threshold_ready_at: i64,
+    // Most lamports that can be withdrawn from the escrow per period of
+    // spending_period seconds, zero for no limit.
+    pub spending_limit: u64,
+    pub spending_period: i64,
+    // Lamports withdrawn in the current period, which started at the unix
+    // timestamp period_start.
+    pub spent_in_period: u64,
+    pub period_start: i64,
+    // The only programs transactions may target besides this one, any if
+    // empty.
+    pub allowed_programs: Vec<Pubkey>,
+    // Seconds a transaction flagged for review can't be executed, zero if
+    // owners can't flag transactions.
+    pub review_period: i64,
+}
+
+impl Multisig {
+    // Most bytes needed to store a multisig with `owners` owners and a
+    // description of `description_len` bytes, with every per-owner setting
+    // in use, including the 8 byte account discriminator. Each tag label
+    // takes 4 bytes more than its length and each withdrawal reference 32.
+    pub fn space(owners: usize, description_len: usize) -> usize {
+        8 + // discriminator
+        4 + description_len + // description
+        4 + owners * 32 + // owners
+        8 + // threshold
+        1 + // nonce
+        4 + // owner_set_seqno
+        8 + // lamports
+        8 + // approve_execute_cooldown
+        1 + // paused
+        8 + // pause_threshold
+        8 + // unpause_threshold
+        1 + // invalidate_on_tamper
+        1 + // alert_mode
+        8 + // transaction_count
+        8 + // min_slot_delay
+        8 + // max_mint_amount
+        4 + owners * 8 + // weights
+        1 + // reweight_invalidates
+        4 + owners + // owner_groups
+        1 + // min_groups
+        8 + // transaction_lifetime
+        1 + // expiry_from
+        1 + 32 + // executor
+        1 + 32 + // pending_executor
+        8 + // pending_executor_deadline
+        4 + owners * 32 + // suspended_owners
+        1 + // clamp_threshold_to_active
+        8 + // min_reserve
+        1 + // executor_must_be_owner
+        1 + // emit_per_approval
+        1 + // proposer_cannot_execute
+        4 + owners * 8 + // contributions
+        1 + // weight_by_contribution
+        1 + // reject_trivial_data
+        4 + // tag_labels
+        1 + // add_owner_requires_unanimous
+        8 + // last_heartbeat_at
+        1 + // protect_executor
+        4 + owners * (4 + MAX_OWNER_NAME_LEN) + // names
+        1 + // require_withdraw_reference
+        4 + // withdraw_references
+        32 + // emergency_recipient
+        8 + // emergency_threshold
+        8 + // emergency_delay
+        4 + owners + // emergency_approvals
+        8 + // emergency_initiated_at
+        8 + // reject_threshold
+        8 + // timelock
+        8 + // max_proposal_age
+        1 + 32 + // permanent_owner
+        8 + // max_approvals_per_window
+        8 + // approval_window
+        4 + owners * 8 + // approvals_this_window
+        4 + owners * 8 + // window_starts
+        4 + // vaults
+        8 + // threshold_decrease_delay
+        8 + // pending_threshold
+        8 + // pending_threshold_ready_at
+        8 + // spending_limit
+        8 + // spending_period
+        8 + // spent_in_period
+        8 + // period_start
+        4 + // allowed_programs
+        8 // review_period
+    }
+
+    // Address and bump of the vault with the given index, for the multisig at
+    // the given address.
+    pub fn vault(&self, multisig: &Pubkey, index: u8) -> Result<(Pubkey, u8)> {
+        let vault = self
+            .vaults
+            .get(index as usize)
+            .ok_or(ErrorCode::InvalidVault)?;
+        let address = Pubkey::create_program_address(
+            &[
+                multisig.as_ref(),
+                VAULT_SEED,
+                &index.to_le_bytes(),
+                &[vault.bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| ErrorCode::InvalidVault)?;
+        Ok((address, vault.bump))
+    }
+
+    // Voting weight of the owner at the given index, their contribution if
+    // weighing by contribution.
+    pub fn owner_weight(&self, index: usize) -> u64 {
+        if self.weight_by_contribution {
+            return self.contributions.get(index).copied().unwrap_or(0);
+        }
+        self.weights.get(index).copied().unwrap_or(1)
+    }
+
+    // True if transactions may target the given program.
+    pub fn allows_program(&self, program_id: &Pubkey) -> bool {
+        self.allowed_programs.is_empty()
+            || program_id == &crate::ID
+            || self.allowed_programs.contains(program_id)
+    }
+
+    // Sum of all the owners' weights, the highest reachable threshold.
+    pub fn total_weight(&self) -> u64 {
+        (0..self.owners.len()).fold(0u64, |total, index| {
+            total.saturating_add(self.owner_weight(index))
+        })
+    }
+
+    // Whether the owner at the given index is not suspended.
+    pub fn is_active(&self, index: usize) -> bool {
+        !self.suspended_owners.contains(&self.owners[index])
+    }
+
+    // Sum of the weights of the owners who aren't suspended.
+    pub fn active_weight(&self) -> u64 {
+        (0..self.owners.len())
+            .filter(|index| self.is_active(*index))
+            .fold(0u64, |total, index| {
+                total.saturating_add(self.owner_weight(index))
+            })
+    }
+
+    // Sum of the weights of the active owners who signed, where
+    // signers[index] is true iff owners[index] signed.
+    pub fn approval_weight(&self, signers: &[bool]) -> u64 {
+        signers
+            .iter()
+            .enumerate()
+            .filter(|(index, did_sign)| **did_sign && self.is_active(*index))
+            .fold(0u64, |total, (index, _)| {
+                total.saturating_add(self.owner_weight(index))
+            })
+    }
+
+    // Sum of the weights of the active owners in the signer set.
+    pub fn signer_weight(&self, signers: &SignerSet) -> u64 {
+        (0..self.owners.len())
+            .filter(|index| signers.get(*index) && self.is_active(*index))
+            .fold(0u64, |total, index| {
+                total.saturating_add(self.owner_weight(index))
+            })
+    }
+
+    // Number of distinct owner groups among the owners who signed.
+    pub fn group_spread(&self, signers: &SignerSet) -> usize {
+        let mut groups: Vec<u8> = self
+            .owner_groups
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| signers.get(*index))
+            .map(|(_, group)| *group)
+            .collect();
+        groups.sort_unstable();
+        groups.dedup();
+        groups.len()
+    }
 }
 
 // TODO: Document
@@ -307,26 +4663,289 @@ pub struct Transaction {
     pub accounts: Vec<TransactionAccount>,
     // Instruction data for the transaction.
     pub data: Vec<u8>,
-    // signers[index] is true iff multisig.owners[index] signed the transaction.
-    pub signers: Vec<bool>,
+    // Bit index is set iff multisig.owners[index] signed the transaction.
+    pub signers: SignerSet,
     // Boolean ensuring one time execution.
     pub did_execute: bool,
     // Owner set sequence number.
     pub owner_set_seqno: u32,
+    // Frozen transactions can't be executed until unfrozen.
+    pub frozen: bool,
+    // The owner who most recently approved the transaction and when.
+    pub last_approver: Pubkey,
+    pub last_approval_at: i64,
+    // Durable nonce account advanced before the proposal executes, if any.
+    pub durable_nonce: Option<Pubkey>,
+    // Hash of the proposal taken at creation, see compute_content_hash.
+    pub content_hash: [u8; 32],
+    // Number of times approvals were reset because the content changed.
+    pub tamper_seqno: u32,
+    // Bump of the transaction's address if created by create_transaction_pda.
+    pub bump: u8,
+    // Slot at which the threshold was first reached, zero if it hasn't been.
+    pub approved_slot: u64,
+    // Unix timestamp of the transaction's creation.
+    pub created_at: i64,
+    // Whether the transaction's lifetime is measured from its creation or its
+    // first approval, one of the EXPIRY_FROM_* constants.
+    pub expiry_from: u8,
+    // Unix timestamp of the first approve, zero if there hasn't been one. The
+    // proposer's own approval at creation doesn't count.
+    pub first_approved_at: i64,
+    // Whether execution must pass exactly the transaction's accounts, see
+    // create_locked_transaction.
+    pub accounts_locked: bool,
+    // approved_at[index] is the unix timestamp of multisig.owners[index]'s
+    // approval, zero if they haven't approved.
+    pub approved_at: Vec<i64>,
+    // The owner who created the transaction.
+    pub proposer: Pubkey,
+    // Sequential id of the transaction within its multisig, the multisig's
+    // transaction count at creation.
+    pub id: u64,
+    // First 8 bytes of the content hash, for display.
+    pub short_hash: [u8; 8],
+    // rejecters[index] is true if multisig.owners[index] rejected the
+    // transaction.
+    pub rejecters: Vec<bool>,
+    // Unix timestamp from which the transaction may be executed under the
+    // multisig's timelock, zero if it hasn't reached its threshold.
+    pub ready_at: i64,
+    // The instructions of a batch, executed in order instead of program_id,
+    // accounts and data. Empty unless created by create_transaction_batch.
+    pub instructions: Vec<TransactionInstruction>,
+    // Who executed the transaction, the default pubkey until then.
+    pub executor: Pubkey,
+    // Where the transaction is in its lifecycle. did_execute is kept in step
+    // for existing clients.
+    pub status: TransactionStatus,
+    // Threshold the proposer required above the multisig's, if any.
+    pub threshold_override: Option<u64>,
+    // Owner set and threshold the transaction was executed under, kept for
+    // audits whatever the multisig's later changes. Empty and zero until it's
+    // executed.
+    pub executed_owners: Vec<Pubkey>,
+    pub executed_threshold: u64,
+    // Unix timestamp until which the transaction is held for review, zero if
+    // it was never flagged.
+    pub review_hold_until: i64,
+    // Slot the target program was last deployed at when proposed, if the
+    // proposer pinned it. Execution fails once the program is upgraded.
+    pub target_program_slot: Option<u64>,
+}
+
+impl Transaction {
+    // Number of bytes needed to store a transaction for a multisig with
+    // `owners` owners wrapping an instruction with `accounts` accounts and
+    // `data_len` bytes of data, including the 8 byte account discriminator.
+    // Batches need TransactionInstruction::space more for each instruction.
+    pub fn space(owners: usize, accounts: usize, data_len: usize) -> usize {
+        8 + // discriminator
+        32 + // multisig
+        32 + // program_id
+        4 + accounts * TransactionAccount::SIZE + // accounts
+        4 + data_len + // data
+        SignerSet::SIZE + // signers
+        1 + // did_execute
+        4 + // owner_set_seqno
+        1 + // frozen
+        32 + // last_approver
+        8 + // last_approval_at
+        1 + 32 + // durable_nonce
+        32 + // content_hash
+        4 + // tamper_seqno
+        1 + // bump
+        8 + // approved_slot
+        8 + // created_at
+        1 + // expiry_from
+        8 + // first_approved_at
+        1 + // accounts_locked
+        4 + owners * 8 + // approved_at
+        32 + // proposer
+        8 + // id
+        8 + // short_hash
+        4 + owners + // rejecters
+        8 + // ready_at
+        4 + // instructions
+        32 + // executor
+        1 + // status
+        9 + // threshold_override
+        4 + owners * 32 + // executed_owners
+        8 + // executed_threshold
+        8 + // review_hold_until
+        9 // target_program_slot
+    }
+
+    // Estimated serialized size of a transaction executing this one, in
+    // bytes. Conservative: accounts are assumed distinct, every signer but
+    // the multisig signer has to sign and the data is counted as if carried
+    // along.
+    pub fn execution_size(&self, multisig_signer: &Pubkey) -> usize {
+        let ixs = Vec::<Instruction>::from(self);
+        let accounts = || ixs.iter().flat_map(|ix| ix.accounts.iter());
+        let signatures = 1 + accounts()
+            .filter(|acc| acc.is_signer && &acc.pubkey != multisig_signer)
+            .count();
+        let data_len: usize = ixs.iter().map(|ix| ix.data.len()).sum();
+        // execute_transaction's accounts, this program and the wrapped ones.
+        let keys = 4 + 1 + ixs.len() + accounts().count();
+        // Compact-u16 lengths are counted at their largest, 3 bytes.
+        3 + signatures * 64 + // signatures
+        3 + // message header
+        3 + keys * 32 + // account keys
+        32 + // recent blockhash
+        3 + 1 + // instruction count and program index
+        3 + keys - 1 + // account indices
+        3 + 8 + 1 + // execute_transaction data
+        data_len // wrapped instruction data
+    }
+
+    // When the transaction expires given the multisig's transaction lifetime,
+    // None if it doesn't. A transaction measured from its first approval
+    // doesn't start aging until it gets one.
+    pub fn expires_at(&self, lifetime: i64) -> Option<i64> {
+        if lifetime == 0 {
+            return None;
+        }
+        let start = match self.expiry_from {
+            EXPIRY_FROM_FIRST_APPROVAL if self.first_approved_at == 0 => return None,
+            EXPIRY_FROM_FIRST_APPROVAL => self.first_approved_at,
+            _ => self.created_at,
+        };
+        Some(start.saturating_add(lifetime))
+    }
+
+    // SHA-256 of the proposal's program id, accounts and data, laid out as
+    // they are Borsh serialized: the program id, a u32 little endian account
+    // count followed by each account's pubkey, is_signer and is_writable
+    // bytes, then a u32 little endian data length followed by the data. A
+    // batch's instructions follow, each laid out the same way.
+    pub fn compute_content_hash(&self) -> [u8; 32] {
+        let mut content = Vec::with_capacity(
+            32 + 4 + self.accounts.len() * TransactionAccount::SIZE + 4 + self.data.len(),
+        );
+        let mut append = |program_id: &Pubkey, accounts: &[TransactionAccount], data: &[u8]| {
+            content.extend_from_slice(program_id.as_ref());
+            content.extend_from_slice(&(accounts.len() as u32).to_le_bytes());
+            for account in accounts.iter() {
+                content.extend_from_slice(account.pubkey.as_ref());
+                content.push(account.is_signer as u8);
+                content.push(account.is_writable as u8);
+            }
+            content.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            content.extend_from_slice(data);
+        };
+        append(&self.program_id, &self.accounts, &self.data);
+        for ix in self.instructions.iter() {
+            append(&ix.program_id, &ix.accounts, &ix.data);
+        }
+        solana_program::hash::hash(&content).to_bytes()
+    }
+
+    // Number of owners who approved the transaction.
+    pub fn approval_count(&self) -> u64 {
+        self.signers.count()
+    }
+
+    // True if the transaction invokes the given instruction of this program.
+    // Batches never do, as a whole.
+    pub fn calls(&self, name: &str) -> bool {
+        self.instructions.is_empty() && invokes(&self.program_id, &self.data, name)
+    }
+
+    // True if the transaction, or any instruction of a batch, adds an owner
+    // that isn't among the given ones, through add_owner, set_owners or
+    // set_owners_and_change_threshold.
+    pub fn adds_owner(&self, owners: &[Pubkey]) -> bool {
+        Vec::<Instruction>::from(self).iter().any(|ix| {
+            if invokes(&ix.program_id, &ix.data, "add_owner") {
+                return true;
+            }
+            if !invokes(&ix.program_id, &ix.data, "set_owners")
+                && !invokes(&ix.program_id, &ix.data, "set_owners_and_change_threshold")
+            {
+                return false;
+            }
+            // Both take the new owners as their first argument.
+            let mut args = &ix.data[8..];
+            match Vec::<Pubkey>::deserialize(&mut args) {
+                Ok(new_owners) => new_owners.iter().any(|owner| !owners.contains(owner)),
+                Err(_) => false,
+            }
+        })
+    }
+
+    // The `paused` argument if this is a set_pause transaction.
+    pub fn pause_request(&self) -> Option<bool> {
+        if !self.calls("set_pause") {
+            return None;
+        }
+        self.data.get(8).map(|paused| *paused != 0)
+    }
+}
+
+// We implement the From trait for the Instruction type in order to turn a Transaction type into an Instruction type
+// We consume the Transaction type and convert it into an Instruction type
+impl From<&Transaction> for Vec<Instruction> {
+    fn from(tx: &Transaction) -> Vec<Instruction> {
+        if !tx.instructions.is_empty() {
+            return tx.instructions.iter().map(Instruction::from).collect();
+        }
+        vec![Instruction {
+            program_id: tx.program_id,
+            accounts: tx.accounts.iter().map(AccountMeta::from).collect(),
+            data: tx.data.clone(),
+        }]
+    }
+}
+
+// Settings of create_multisig that have a default.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CreateMultisigArgs {
+    // Reject thresholds below a majority of the owners instead of warning.
+    pub strict: bool,
+    // Where an emergency withdrawal sends the escrow, the default key for
+    // none. Emergency withdrawals take the regular threshold until
+    // set_emergency_policy lowers it.
+    pub emergency_recipient: Pubkey,
+    // The voting weight of each owner, empty for every owner to weigh one.
+    pub weights: Vec<u64>,
+}
+
+// One of the instructions of a batch transaction.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TransactionInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<TransactionAccount>,
+    pub data: Vec<u8>,
 }
 
-// We implement the From trait for the Instruction type in order to turn a Transaction type into an Instruction type
-// We consume the Transaction type and convert it into an Instruction type
-impl From<&Transaction> for Instruction {
-    fn from(tx: &Transaction) -> Instruction {
+impl TransactionInstruction {
+    // Serialized size of the instruction within a batch.
+    pub fn space(&self) -> usize {
+        32 + 4 + self.accounts.len() * TransactionAccount::SIZE + 4 + self.data.len()
+    }
+}
+
+impl From<&TransactionInstruction> for Instruction {
+    fn from(ix: &TransactionInstruction) -> Instruction {
         Instruction {
-            program_id: tx.program_id,
-            accounts: tx.accounts.iter().map(AccountMeta::from).collect(),
-            data: tx.data.clone(),
+            program_id: ix.program_id,
+            accounts: ix.accounts.iter().map(AccountMeta::from).collect(),
+            data: ix.data.clone(),
         }
     }
 }
 
+// Lifecycle of a transaction. Only pending transactions can be executed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum TransactionStatus {
+    Pending,
+    Executed,
+    Cancelled,
+    Expired,
+}
+
 // Accounts that are a aprt of the wrapped transactions that will execute once we have enough votes
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct TransactionAccount {
@@ -335,6 +4954,94 @@ pub struct TransactionAccount {
     pub is_writable: bool,
 }
 
+impl TransactionAccount {
+    // Serialized size of a single account: pubkey, is_signer and is_writable.
+    pub const SIZE: usize = 32 + 1 + 1;
+}
+
+// A signer of its own governed by the multisig's owners, e.g. to segregate
+// operating funds from reserves.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Vault {
+    pub label: String,
+    // Bump of the vault's program address.
+    pub bump: u8,
+}
+
+// Append-only record of a multisig's config changes.
+#[account]
+pub struct ConfigHistory {
+    // The multisig whose config is recorded.
+    pub multisig: Pubkey,
+    pub entries: Vec<ConfigEntry>,
+}
+
+// The multisig's config right after one of its changes.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ConfigEntry {
+    // Position of the entry in the history, starting at one.
+    pub seqno: u64,
+    // SHA-256 of the owners' keys, concatenated in order.
+    pub owners_hash: [u8; 32],
+    pub threshold: u64,
+    // Unix timestamp of the change.
+    pub timestamp: i64,
+}
+
+// Outcome of an execution, returned by execute_transaction.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ExecutionResult {
+    pub executed: bool,
+    // Number of owners who approved the transaction.
+    pub signatures: u64,
+    // Approvals the transaction needed.
+    pub threshold: u64,
+}
+
+// Owners who signed a transaction, one bit per owner index. The bitmap is
+// exactly MAX_OWNERS bits wide.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct SignerSet {
+    pub bits: u64,
+}
+
+impl SignerSet {
+    pub const SIZE: usize = 8;
+
+    pub fn set(&mut self, index: usize) {
+        self.bits |= 1u64 << index;
+    }
+
+    pub fn clear(&mut self, index: usize) {
+        self.bits &= !(1u64 << index);
+    }
+
+    pub fn get(&self, index: usize) -> bool {
+        index < MAX_OWNERS && self.bits & (1u64 << index) != 0
+    }
+
+    // Number of owners in the set.
+    pub fn count(&self) -> u64 {
+        self.bits.count_ones() as u64
+    }
+
+    // Indices of the owners in the set, in increasing order.
+    pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..MAX_OWNERS).filter(move |index| self.get(*index))
+    }
+}
+
+// Self-contained copy of a proposal, returned by serialize_proposal.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ProposalSnapshot {
+    pub program_id: Pubkey,
+    pub accounts: Vec<TransactionAccount>,
+    pub data: Vec<u8>,
+    pub signers: SignerSet,
+    // Approvals needed to execute the proposal.
+    pub threshold: u64,
+}
+
 // We implement the From trait for the TransactionAccount type in order to convert it into an AccountMeta type
 impl From<&TransactionAccount> for AccountMeta {
     fn from(account: &TransactionAccount) -> AccountMeta {
@@ -345,6 +5052,16 @@ impl From<&TransactionAccount> for AccountMeta {
     }
 }
 
+impl From<&Instruction> for TransactionInstruction {
+    fn from(ix: &Instruction) -> TransactionInstruction {
+        TransactionInstruction {
+            program_id: ix.program_id,
+            accounts: ix.accounts.iter().map(TransactionAccount::from).collect(),
+            data: ix.data.clone(),
+        }
+    }
+}
+
 // We implement the From trait for the AccountMeta type in order to convert it into an TransactionAccount type
 impl From<&AccountMeta> for TransactionAccount {
     fn from(account_meta: &AccountMeta) -> TransactionAccount {
@@ -356,6 +5073,536 @@ impl From<&AccountMeta> for TransactionAccount {
     }
 }
 
+// ***** Events ***** //
+// Only IDL types are used, so clients can decode the events with the
+// program's event coder, e.g. through program.addEventListener.
+
+// A multisig was created, with its initial config.
+#[event]
+pub struct MultisigCreated {
+    pub multisig: Pubkey,
+    pub owners: Vec<Pubkey>,
+    // Empty while every owner weighs one.
+    pub weights: Vec<u64>,
+    pub threshold: u64,
+    pub description: String,
+}
+
+// A transaction was proposed, see init_transaction.
+#[event]
+pub struct TransactionCreated {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub proposer: Pubkey,
+    // Program the transaction invokes.
+    pub program_id: Pubkey,
+    // The multisig's transaction count when the transaction was created.
+    pub index: u64,
+}
+
+// An owner approved a transaction.
+#[event]
+pub struct TransactionApproved {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub owner: Pubkey,
+    // Weight of the transaction's approvals, including this one.
+    pub approval_weight: u64,
+    // Number of owners who approved, including this one.
+    pub approvals: u64,
+    // Approval weight the transaction needs to be executed.
+    pub threshold: u64,
+}
+
+// An owner withdrew their approval of a transaction, rejecting it if
+// rejected is set.
+#[event]
+pub struct ApprovalRevoked {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub owner: Pubkey,
+    pub rejected: bool,
+    // Weight of the transaction's remaining approvals.
+    pub approval_weight: u64,
+}
+
+// An owner moved the approvals of an expired or cancelled source onto a
+// transaction with the same content. The source was cancelled.
+#[event]
+pub struct ApprovalsImported {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub source: Pubkey,
+    pub owner: Pubkey,
+    // Number of approvals the transaction gained.
+    pub imported: u64,
+}
+
+// An owner held a transaction for review until hold_until.
+#[event]
+pub struct TransactionFlagged {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub owner: Pubkey,
+    pub hold_until: i64,
+}
+
+// An executed transaction was closed by prune_executed, its rent going to
+// the collector.
+#[event]
+pub struct TransactionPruned {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub collector: Pubkey,
+}
+
+// The multisig froze or unfroze a transaction.
+#[event]
+pub struct TransactionFrozen {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub frozen: bool,
+}
+
+// The multisig cancelled a transaction.
+#[event]
+pub struct TransactionCancelled {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+}
+
+// The proposer deleted a transaction, closing its account.
+#[event]
+pub struct TransactionDeleted {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub proposer: Pubkey,
+}
+
+// A transaction was executed.
+#[event]
+pub struct TransactionExecuted {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub executor: Pubkey,
+}
+
+// A transaction was executed by a multisig that doesn't emit per approval
+// events, summarizing who approved it and when.
+#[event]
+pub struct ApprovalBatchSummary {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub signers: Vec<Pubkey>,
+    // approved_at[index] is the unix timestamp of signers[index]'s approval.
+    pub approved_at: Vec<i64>,
+}
+
+// An owner deposited lamports into escrow.
+#[event]
+pub struct LamportsDeposited {
+    pub multisig: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    // Escrowed lamports after the deposit.
+    pub escrow: u64,
+}
+
+// An owner deposited tokens into the multisig signer's token account.
+#[event]
+pub struct TokensDeposited {
+    pub multisig: Pubkey,
+    pub depositor: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+}
+
+// Tokens were withdrawn from the multisig signer's token account.
+#[event]
+pub struct TokensWithdrawn {
+    pub multisig: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+// Escrowed lamports were moved into another multisig's escrow.
+#[event]
+pub struct EscrowTransferred {
+    pub multisig: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+// Escrowed lamports were withdrawn to the destination.
+#[event]
+pub struct LamportsWithdrawn {
+    pub multisig: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    // Reference the withdrawal was made under, for reconciliation.
+    pub reference: [u8; 32],
+}
+
+// The multisig executed a heartbeat, proving it's still controllable.
+#[event]
+pub struct Heartbeat {
+    pub multisig: Pubkey,
+    pub timestamp: i64,
+}
+
+// The multisig signs with a new signer, see rotate_signer.
+#[event]
+pub struct SignerRotated {
+    pub multisig: Pubkey,
+    pub old_signer: Pubkey,
+    pub new_signer: Pubkey,
+}
+
+// The multisig was paused or unpaused.
+#[event]
+pub struct PauseChanged {
+    pub multisig: Pubkey,
+    pub paused: bool,
+}
+
+// Alert mode was turned on or off.
+#[event]
+pub struct AlertModeChanged {
+    pub multisig: Pubkey,
+    pub alert_mode: bool,
+}
+
+// One of the delays between approval and execution changed. Carries all of
+// them after the change.
+#[event]
+pub struct ExecutionDelaysChanged {
+    pub multisig: Pubkey,
+    pub min_slot_delay: u64,
+    pub timelock: i64,
+    pub approve_execute_cooldown: i64,
+}
+
+// A vault was created at the given index.
+#[event]
+pub struct VaultCreated {
+    pub multisig: Pubkey,
+    pub index: u8,
+    pub vault: Pubkey,
+    pub label: String,
+}
+
+// An owner was suspended or reinstated.
+#[event]
+pub struct OwnerSuspensionChanged {
+    pub multisig: Pubkey,
+    pub owner: Pubkey,
+    pub suspended: bool,
+}
+
+// The multisig's permanent owner was set.
+#[event]
+pub struct PermanentOwnerSet {
+    pub multisig: Pubkey,
+    pub owner: Pubkey,
+}
+
+// The owners' names were replaced.
+#[event]
+pub struct OwnerNamesChanged {
+    pub multisig: Pubkey,
+    pub names: Vec<String>,
+}
+
+// A tag's label was set, an empty one clearing it.
+#[event]
+pub struct TagLabelChanged {
+    pub multisig: Pubkey,
+    pub tag: u8,
+    pub label: String,
+}
+
+// An executor was proposed, to accept by deadline, zero if it has none.
+#[event]
+pub struct ExecutorProposed {
+    pub multisig: Pubkey,
+    pub executor: Pubkey,
+    pub deadline: i64,
+}
+
+// The proposed executor accepted the role.
+#[event]
+pub struct ExecutorAccepted {
+    pub multisig: Pubkey,
+    pub executor: Pubkey,
+}
+
+// An owner approved an emergency withdrawal. initiated_at is set, non-zero,
+// once the approvals reach the emergency threshold.
+#[event]
+pub struct EmergencyApproved {
+    pub multisig: Pubkey,
+    pub owner: Pubkey,
+    // Weight of the emergency approvals, including this one.
+    pub approval_weight: u64,
+    pub initiated_at: i64,
+}
+
+// An owner cancelled the emergency withdrawal, clearing its approvals.
+#[event]
+pub struct EmergencyCancelled {
+    pub multisig: Pubkey,
+    pub owner: Pubkey,
+}
+
+// A config history was created for the multisig.
+#[event]
+pub struct ConfigHistoryCreated {
+    pub multisig: Pubkey,
+    pub history: Pubkey,
+}
+
+// The delay applied to threshold decreases changed.
+#[event]
+pub struct ThresholdDecreaseDelayChanged {
+    pub multisig: Pubkey,
+    pub old_delay: i64,
+    pub delay: i64,
+}
+
+// The escrowed lamports withdrawals must leave behind changed.
+#[event]
+pub struct MinReserveChanged {
+    pub multisig: Pubkey,
+    pub old_min_reserve: u64,
+    pub min_reserve: u64,
+}
+
+// The programs transactions may target changed, empty for any.
+#[event]
+pub struct AllowedProgramsChanged {
+    pub multisig: Pubkey,
+    pub old_programs: Vec<Pubkey>,
+    pub programs: Vec<Pubkey>,
+}
+
+// Whether a tampered transaction has its approvals reset, rather than
+// failing at execution, changed.
+#[event]
+pub struct InvalidateOnTamperChanged {
+    pub multisig: Pubkey,
+    pub old_invalidate: bool,
+    pub invalidate: bool,
+}
+
+// The most a single propose_mint_to proposal may mint changed.
+#[event]
+pub struct MaxMintAmountChanged {
+    pub multisig: Pubkey,
+    pub old_max_mint_amount: u64,
+    pub max_mint_amount: u64,
+}
+
+// Whether set_weight invalidates pending transactions changed.
+#[event]
+pub struct ReweightInvalidatesChanged {
+    pub multisig: Pubkey,
+    pub old_invalidates: bool,
+    pub invalidates: bool,
+}
+
+// Whether owners weigh their escrow contributions changed.
+#[event]
+pub struct WeightByContributionChanged {
+    pub multisig: Pubkey,
+    pub old_enabled: bool,
+    pub enabled: bool,
+}
+
+// Whether proposals with trivial data are rejected changed.
+#[event]
+pub struct RejectTrivialDataChanged {
+    pub multisig: Pubkey,
+    pub old_enabled: bool,
+    pub enabled: bool,
+}
+
+// Whether adding owners needs every owner's approval changed.
+#[event]
+pub struct AddOwnerRequiresUnanimousChanged {
+    pub multisig: Pubkey,
+    pub old_enabled: bool,
+    pub enabled: bool,
+}
+
+// The maximum proposal age changed.
+#[event]
+pub struct MaxProposalAgeChanged {
+    pub multisig: Pubkey,
+    pub old_max_proposal_age: i64,
+    pub max_proposal_age: i64,
+}
+
+// Whether the required threshold is capped at the active owners' weight
+// changed.
+#[event]
+pub struct ClampThresholdToActiveChanged {
+    pub multisig: Pubkey,
+    pub old_enabled: bool,
+    pub enabled: bool,
+}
+
+// The weight of rejections that blocks a transaction changed.
+#[event]
+pub struct RejectThresholdChanged {
+    pub multisig: Pubkey,
+    pub old_reject_threshold: u64,
+    pub reject_threshold: u64,
+}
+
+// Whether only owners may execute changed.
+#[event]
+pub struct ExecutorMustBeOwnerChanged {
+    pub multisig: Pubkey,
+    pub old_enabled: bool,
+    pub enabled: bool,
+}
+
+// Whether every approval emits an event changed.
+#[event]
+pub struct EmitPerApprovalChanged {
+    pub multisig: Pubkey,
+    pub old_enabled: bool,
+    pub enabled: bool,
+}
+
+// Whether proposers are barred from executing changed.
+#[event]
+pub struct ProposerCannotExecuteChanged {
+    pub multisig: Pubkey,
+    pub old_enabled: bool,
+    pub enabled: bool,
+}
+
+// Whether transactions writing to their executor's account are refused
+// changed.
+#[event]
+pub struct ProtectExecutorChanged {
+    pub multisig: Pubkey,
+    pub old_enabled: bool,
+    pub enabled: bool,
+}
+
+// The review period flagged transactions are held for changed.
+#[event]
+pub struct ReviewPeriodChanged {
+    pub multisig: Pubkey,
+    pub old_review_period: i64,
+    pub review_period: i64,
+}
+
+// The approval throttle changed, restarting every owner's window.
+#[event]
+pub struct ApprovalThrottleChanged {
+    pub multisig: Pubkey,
+    pub old_max_approvals_per_window: u64,
+    pub old_approval_window: i64,
+    pub max_approvals_per_window: u64,
+    pub approval_window: i64,
+}
+
+// The spending limit changed, restarting its period.
+#[event]
+pub struct SpendingLimitChanged {
+    pub multisig: Pubkey,
+    pub old_spending_limit: u64,
+    pub old_spending_period: i64,
+    pub spending_limit: u64,
+    pub spending_period: i64,
+}
+
+// The emergency withdrawal threshold or delay changed.
+#[event]
+pub struct EmergencyPolicyChanged {
+    pub multisig: Pubkey,
+    pub old_threshold: u64,
+    pub old_delay: i64,
+    pub threshold: u64,
+    pub delay: i64,
+}
+
+// The withdrawal reference policy changed.
+#[event]
+pub struct WithdrawReferencePolicyChanged {
+    pub multisig: Pubkey,
+    pub old_required: bool,
+    pub old_references: Vec<[u8; 32]>,
+    pub required: bool,
+    pub references: Vec<[u8; 32]>,
+}
+
+// The thresholds of set_pause transactions changed.
+#[event]
+pub struct PauseThresholdsChanged {
+    pub multisig: Pubkey,
+    pub old_pause_threshold: u64,
+    pub old_unpause_threshold: u64,
+    pub pause_threshold: u64,
+    pub unpause_threshold: u64,
+}
+
+// The owner groups or the groups an approval must span changed.
+#[event]
+pub struct OwnerGroupsChanged {
+    pub multisig: Pubkey,
+    pub old_owner_groups: Vec<u8>,
+    pub old_min_groups: u8,
+    pub owner_groups: Vec<u8>,
+    pub min_groups: u8,
+}
+
+// The transaction lifetime or where it's measured from changed.
+#[event]
+pub struct TransactionLifetimeChanged {
+    pub multisig: Pubkey,
+    pub old_lifetime: i64,
+    pub old_expiry_from: u8,
+    pub lifetime: i64,
+    pub expiry_from: u8,
+}
+
+// The multisig was closed, its rent going to the destination.
+#[event]
+pub struct MultisigClosed {
+    pub multisig: Pubkey,
+    pub destination: Pubkey,
+}
+
+// A threshold decrease was recorded, to be applied by apply_threshold_change
+// from ready_at.
+#[event]
+pub struct ThresholdDecreaseScheduled {
+    pub multisig: Pubkey,
+    pub threshold: u64,
+    pub pending_threshold: u64,
+    pub ready_at: i64,
+}
+
+// The multisig's owners, weights, threshold or description changed. Carries
+// the config right after the change.
+#[event]
+pub struct ConfigChanged {
+    pub multisig: Pubkey,
+    pub owners: Vec<Pubkey>,
+    // Empty while every owner weighs one.
+    pub weights: Vec<u64>,
+    pub threshold: u64,
+    pub owner_set_seqno: u32,
+    pub description: String,
+}
+
 // ***** Errors ***** //
 #[error]
 pub enum ErrorCode {
@@ -373,5 +5620,234 @@ pub enum ErrorCode {
     AlreadyExecuted,
     #[msg("Threshold must be less than or equal to the number of owners.")]
     InvalidThreshold,
-    // TODO: add new errors for depositing and withdrawing lamports
+    #[msg("The transaction account is too small to hold the proposal.")]
+    AccountTooSmall,
+    #[msg("The given transaction is frozen.")]
+    TransactionFrozen,
+    #[msg("The latest approver can't execute the transaction during the cooldown.")]
+    SameSignerApproveExecute,
+    #[msg("The nonce account must be an initialized nonce owned by the multisig signer.")]
+    InvalidNonceAccount,
+    #[msg("The multisig is paused.")]
+    MultisigPaused,
+    #[msg("The transaction's signers are inconsistent with the multisig.")]
+    SignerCountMismatch,
+    #[msg("The transaction's content doesn't match its content hash.")]
+    ContentHashMismatch,
+    #[msg("The data to return exceeds the return data limit.")]
+    ReturnDataTooLarge,
+    #[msg("The threshold can't be lowered while in alert mode.")]
+    ThresholdDecreaseBlocked,
+    #[msg("The transaction index must equal the multisig's transaction count.")]
+    InvalidTransactionIndex,
+    #[msg("Not enough slots have passed since the transaction was approved.")]
+    SlotDelayNotElapsed,
+    #[msg("The amount exceeds the multisig's mint cap.")]
+    MintCapExceeded,
+    #[msg("Owner weights must be greater than zero.")]
+    InvalidWeight,
+    #[msg("The owner groups don't match the owners or can't reach the minimum spread.")]
+    InvalidOwnerGroups,
+    #[msg("The approvals don't span enough owner groups.")]
+    InsufficientGroupSpread,
+    #[msg("The transaction lifetime or expiry anchor is invalid.")]
+    InvalidExpiry,
+    #[msg("The transaction has expired.")]
+    TransactionExpired,
+    #[msg("The account isn't a multisig.")]
+    InvalidMultisigAccount,
+    #[msg("The signer isn't the pending executor.")]
+    InvalidPendingExecutor,
+    #[msg("The executor handoff has expired.")]
+    ExecutorHandoffExpired,
+    #[msg("Only the multisig's executor can execute transactions.")]
+    UnauthorizedExecutor,
+    #[msg("The escrow doesn't hold enough lamports.")]
+    InsufficientEscrow,
+    #[msg("The new authority doesn't fit into the instruction data at the given offset.")]
+    InvalidAuthorityOffset,
+    #[msg("At least one owner must remain active.")]
+    NoActiveOwners,
+    #[msg("The config history doesn't belong to the multisig.")]
+    InvalidConfigHistory,
+    #[msg("The withdrawal would leave the escrow below its reserve.")]
+    ReserveBreached,
+    #[msg("The transaction was created for a previous set of owners.")]
+    StaleTransaction,
+    #[msg("The accounts passed don't match the locked transaction's accounts.")]
+    AccountSubstitutionDetected,
+    #[msg("The threshold is below a majority of the owners.")]
+    ThresholdBelowMajority,
+    #[msg("The transaction's proposer can't execute it.")]
+    ProposerCannotExecute,
+    #[msg("The source must be another transaction of the multisig that expired or was cancelled.")]
+    InvalidSourceTransaction,
+    #[msg("The account isn't a transaction.")]
+    InvalidTransactionAccount,
+    #[msg("The instruction data is empty or a single repeated byte.")]
+    SuspiciousInstructionData,
+    #[msg("The tag label is too long.")]
+    TagLabelTooLong,
+    #[msg("The owner is already part of this multisig.")]
+    DuplicateOwner,
+    #[msg("Adding an owner requires the approval of every current owner.")]
+    UnanimityRequired,
+    #[msg("Keeping approvals across an owner replacement must be acknowledged.")]
+    RiskNotAcknowledged,
+    #[msg("The nonce doesn't derive a valid multisig signer address.")]
+    InvalidNonce,
+    #[msg("The transaction would write to the executor's account.")]
+    ExecutorAccountMutation,
+    #[msg("There must be one name per owner, each within the length limit.")]
+    InvalidOwnerNames,
+    #[msg("Deposits must be of at least one lamport.")]
+    DepositTooSmall,
+    #[msg("Withdrawals must be made under a non-zero reference.")]
+    MissingWithdrawReference,
+    #[msg("The withdrawal reference isn't one of the pre-authorized ones.")]
+    UnauthorizedWithdrawReference,
+    #[msg("The multisig has no emergency recipient.")]
+    NoEmergencyRecipient,
+    #[msg("An emergency withdrawal is already in progress.")]
+    EmergencyInProgress,
+    #[msg("No emergency withdrawal has been initiated.")]
+    EmergencyNotInitiated,
+    #[msg("The emergency withdrawal delay hasn't elapsed yet.")]
+    EmergencyDelayNotElapsed,
+    #[msg("The emergency delay can't be negative.")]
+    InvalidEmergencyDelay,
+    #[msg("The transaction was rejected by enough owners.")]
+    TransactionRejected,
+    #[msg("The account isn't an SPL mint or token account.")]
+    InvalidAuthorityAccount,
+    #[msg("The timelock can't be negative.")]
+    InvalidTimelock,
+    #[msg("The transaction's timelock hasn't elapsed yet.")]
+    TimelockNotElapsed,
+    #[msg("The permanent owner can't be removed or changed.")]
+    CannotRemovePermanentOwner,
+    #[msg("The multisig can't have more owners.")]
+    TooManyOwners,
+    #[msg("The owner approved too many transactions in the current window.")]
+    ApprovalRateExceeded,
+    #[msg("A batch must have at least one instruction.")]
+    EmptyBatch,
+    #[msg("A token account owned by the multisig still holds tokens.")]
+    TokenBalanceRemaining,
+    #[msg("The vault label is too long.")]
+    VaultLabelTooLong,
+    #[msg("The multisig can't have any more vaults.")]
+    TooManyVaults,
+    #[msg("The multisig has no vault with the given index.")]
+    InvalidVault,
+    #[msg("There must be one weight per owner.")]
+    WeightsMismatch,
+    #[msg("The multisig's last owner can't be removed.")]
+    LastOwner,
+    #[msg("The threshold decrease delay hasn't passed yet.")]
+    ThresholdChangePending,
+    #[msg("No threshold decrease is pending.")]
+    NoPendingThresholdChange,
+    #[msg("The owner set changed since the transaction was created.")]
+    OwnerSetChanged,
+    #[msg("The token account isn't held by the multisig signer for the given mint.")]
+    InvalidTokenAccount,
+    #[msg("The transaction has been cancelled.")]
+    TransactionCancelled,
+    #[msg("The instruction data exceeds MAX_TX_DATA_LEN.")]
+    DataTooLarge,
+    #[msg("The instruction has more than MAX_TX_ACCOUNTS accounts.")]
+    TooManyAccounts,
+    #[msg("The escrow still holds lamports.")]
+    EscrowNotEmpty,
+    #[msg("The withdrawal would exceed the spending limit for the period.")]
+    SpendingLimitExceeded,
+    #[msg("The multisig doesn't allow transactions targeting this program.")]
+    ProgramNotAllowed,
+    #[msg("Lamports must be moved out of the multisig through its withdraw instructions.")]
+    UseWithdrawInstruction,
+    #[msg("The transaction is older than the multisig's maximum proposal age.")]
+    TransactionTooOld,
+    #[msg("The accounts given don't match the current owners, in order.")]
+    OwnerListMismatch,
+    #[msg("The multisig has no review period to flag transactions for.")]
+    ReviewNotEnabled,
+    #[msg("The transaction was already flagged for review.")]
+    AlreadyFlagged,
+    #[msg("The transaction is held for review.")]
+    TransactionUnderReview,
+    #[msg("The withdrawal doesn't add up to the escrowed lamports.")]
+    LamportsNotConserved,
+    #[msg("The account isn't the target program's program data account.")]
+    InvalidProgramData,
+    #[msg("The target program was upgraded since the transaction was proposed.")]
+    TargetProgramChanged,
+    #[msg("The owners can't be replaced while they're weighed by their contributions.")]
+    WeighedByContribution,
+    #[msg("The owner hasn't approved this transaction.")]
+    NotApproved,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_conserved(total: u64, owners: usize) {
+        let shares = split_escrow(total, owners).unwrap();
+        assert_eq!(shares.len(), owners);
+        let share = total / owners as u64;
+        assert_eq!(shares[0], share + total % owners as u64);
+        assert!(shares[1..].iter().all(|s| *s == share));
+        let credited: u128 = shares.iter().map(|s| *s as u128).sum();
+        assert_eq!(credited, total as u128);
+    }
+
+    #[test]
+    fn split_escrow_rejects_no_owners() {
+        assert_eq!(
+            split_escrow(1000, 0).map_err(ProgramError::from),
+            Err(ErrorCode::OwnerListMismatch.into())
+        );
+        assert_eq!(
+            split_escrow(0, 0).map_err(ProgramError::from),
+            Err(ErrorCode::OwnerListMismatch.into())
+        );
+    }
+
+    #[test]
+    fn split_escrow_gives_the_remainder_to_the_first_owner() {
+        // A pool just short of one lamport per owner is all remainder.
+        assert_eq!(split_escrow(63, 64).unwrap()[..2], [63, 0]);
+        assert_conserved(63, 64);
+        assert_conserved(65, 64);
+        assert_conserved(0, 3);
+    }
+
+    #[test]
+    fn split_escrow_doesnt_overflow_near_u64_max() {
+        assert_eq!(split_escrow(u64::MAX, 1).unwrap(), vec![u64::MAX]);
+        assert_conserved(u64::MAX, 2);
+        assert_conserved(u64::MAX, MAX_OWNERS);
+        assert_conserved(u64::MAX - 1, MAX_OWNERS - 1);
+    }
+
+    #[test]
+    fn split_escrow_conserves_lamports() {
+        // Knuth's MMIX linear congruential generator, seeded for
+        // reproducibility.
+        let mut state: u64 = 0x5eed;
+        let mut next = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            state
+        };
+        for owners in 1..=MAX_OWNERS {
+            for _ in 0..64 {
+                // Spread the pools over every magnitude, not just large ones.
+                let total = next() >> (next() % 64);
+                assert_conserved(total, owners);
+            }
+        }
+    }
 }
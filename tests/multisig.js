@@ -1,5 +1,7 @@
 const anchor = require("@project-serum/anchor");
 const assert = require("assert");
+const crypto = require("crypto");
+const { Token, TOKEN_PROGRAM_ID } = require("@solana/spl-token");
 
 describe("multisig", () => {
   // Configure the client to use the local cluster.
@@ -7,6 +9,246 @@ describe("multisig", () => {
 
   const program = anchor.workspace.SerumMultisig;
 
+  // Creates a multisig governed by the given owners, returning its keypair,
+  // the PDA signer and the nonce used to derive it. With `strict`, thresholds
+  // below a majority are rejected rather than warned about.
+  async function createMultisig(
+    owners,
+    threshold,
+    size = 1000,
+    strict = false,
+    emergencyRecipient = anchor.web3.PublicKey.default,
+    weights = []
+  ) {
+    const multisig = anchor.web3.Keypair.generate();
+    const [
+      multisigSigner,
+      nonce,
+    ] = await anchor.web3.PublicKey.findProgramAddress(
+      [multisig.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.rpc.createMultisig(
+      "test multisig",
+      owners,
+      new anchor.BN(threshold),
+      nonce,
+      {
+        strict,
+        emergencyRecipient,
+        weights: weights.map((weight) => new anchor.BN(weight)),
+      },
+      {
+        accounts: {
+          multisig: multisig.publicKey,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        },
+        instructions: [
+          await program.account.multisig.createInstruction(multisig, size),
+        ],
+        signers: [multisig],
+      }
+    );
+    return { multisig, multisigSigner, nonce };
+  }
+
+  // Reference for withdrawals that aren't made under any in particular.
+  const noReference = Array(32).fill(0);
+
+  // Proposes a transaction on the multisig, returning the transaction keypair.
+  async function createTransaction(
+    multisig,
+    proposer,
+    pid,
+    accounts,
+    data,
+    size = 1000,
+    transaction = anchor.web3.Keypair.generate()
+  ) {
+    await program.rpc.createTransaction(pid, accounts, data, null, {
+      accounts: {
+        multisig: multisig.publicKey,
+        transaction: transaction.publicKey,
+        proposer: proposer.publicKey,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      },
+      instructions: [
+        await program.account.transaction.createInstruction(transaction, size),
+      ],
+      signers: [transaction, proposer],
+    });
+    return transaction;
+  }
+
+  // Accounts wrapped by a proposal that invokes one of this program's own
+  // governance instructions through the multisig signer.
+  function authAccounts(multisig, multisigSigner, extra = []) {
+    return [
+      { pubkey: multisig.publicKey, isWritable: true, isSigner: false },
+      { pubkey: multisigSigner, isWritable: false, isSigner: true },
+    ].concat(extra);
+  }
+
+  // Approves the transaction on behalf of the given owner.
+  async function approve(multisig, transaction, owner) {
+    await program.rpc.approve({
+      accounts: {
+        multisig: multisig.publicKey,
+        transaction: transaction.publicKey,
+        owner: owner.publicKey,
+      },
+      signers: [owner],
+    });
+  }
+
+  // Executes a transaction wrapping the given accounts. The multisig signer
+  // is signed for by the program, so it's passed as a non-signer. Options:
+  // `pid` of the wrapped program, an `executor` keypair (defaults to the
+  // provider wallet), `extraAccounts` appended to the remaining accounts and
+  // the `vaultIndex` of a vault to sign with.
+  async function executeTransaction(
+    multisig,
+    multisigSigner,
+    transaction,
+    accounts,
+    opts = {}
+  ) {
+    const pid = opts.pid || program.programId;
+    const executor = opts.executor;
+    const vaultIndex = opts.vaultIndex === undefined ? null : opts.vaultIndex;
+    return await program.rpc.executeTransaction(!!opts.profile, vaultIndex, {
+      accounts: {
+        multisig: multisig.publicKey,
+        multisigSigner,
+        transaction: transaction.publicKey,
+        executor: executor
+          ? executor.publicKey
+          : program.provider.wallet.publicKey,
+      },
+      remainingAccounts: accounts
+        .map((meta) =>
+          meta.pubkey.equals(multisigSigner) ? { ...meta, isSigner: false } : meta
+        )
+        .concat({ pubkey: pid, isWritable: false, isSigner: false })
+        .concat(opts.extraAccounts || []),
+      signers: (executor ? [executor] : []).concat(opts.signers || []),
+    });
+  }
+
+  // Proposes and executes one of this program's governance instructions on a
+  // 1-of-n multisig whose first owner is `proposer`.
+  async function govern(multisig, multisigSigner, proposer, name, args, extra) {
+    const accounts = authAccounts(multisig, multisigSigner, extra);
+    const data = program.coder.instruction.encode(name, args);
+    const transaction = await createTransaction(
+      multisig,
+      proposer,
+      program.programId,
+      accounts,
+      data
+    );
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+    return transaction;
+  }
+
+  // Client side version of Transaction::compute_content_hash.
+  function contentHash(pid, accounts, data) {
+    const count = Buffer.alloc(4);
+    count.writeUInt32LE(accounts.length);
+    const len = Buffer.alloc(4);
+    len.writeUInt32LE(data.length);
+    return crypto
+      .createHash("sha256")
+      .update(
+        Buffer.concat([
+          pid.toBuffer(),
+          count,
+          ...accounts.map((acc) =>
+            Buffer.concat([
+              acc.pubkey.toBuffer(),
+              Buffer.from([acc.isSigner ? 1 : 0, acc.isWritable ? 1 : 0]),
+            ])
+          ),
+          len,
+          data,
+        ])
+      )
+      .digest();
+  }
+
+  // Decodes the return data of a simulation or transaction meta, padded back
+  // to `length` bytes: the runtime trims trailing zeros off return data,
+  // dropping it altogether when it's all zeros.
+  function decodeReturnData(returnData, length) {
+    if (returnData) {
+      assert.strictEqual(returnData.programId, program.programId.toBase58());
+    }
+    const data = returnData
+      ? Buffer.from(returnData.data[0], "base64")
+      : Buffer.alloc(0);
+    return Buffer.concat([
+      data,
+      Buffer.alloc(Math.max(length - data.length, 0)),
+    ]);
+  }
+
+  // Simulates the instruction, returning its logs as `raw` and the data it
+  // returned through the return data syscall as `returnData`, padded to
+  // `length` bytes.
+  async function simulate(ix, length, signers = []) {
+    const provider = program.provider;
+    const tx = new anchor.web3.Transaction().add(ix);
+    tx.feePayer = provider.wallet.publicKey;
+    tx.recentBlockhash = (
+      await provider.connection.getRecentBlockhash()
+    ).blockhash;
+    if (signers.length) {
+      tx.partialSign(...signers);
+    }
+    await provider.wallet.signTransaction(tx);
+    const { result } = await provider.connection._rpcRequest(
+      "simulateTransaction",
+      [tx.serialize().toString("base64"), { encoding: "base64" }]
+    );
+    const { err, logs, returnData } = result.value;
+    assert.strictEqual(err, null, JSON.stringify(logs));
+    return { raw: logs, returnData: decodeReturnData(returnData, length) };
+  }
+
+  // The return data recorded in a confirmed transaction's meta, padded to
+  // `length` bytes.
+  async function transactionReturnData(signature, length) {
+    const connection = program.provider.connection;
+    await connection.confirmTransaction(signature, "confirmed");
+    const { result } = await connection._rpcRequest("getTransaction", [
+      signature,
+      { commitment: "confirmed", encoding: "json" },
+    ]);
+    return decodeReturnData(result.meta.returnData, length);
+  }
+
+  // Unpacks a transaction's signer bitmap into one flag per owner.
+  function signedBy(txAccount) {
+    return txAccount.approvedAt.map((_, index) =>
+      txAccount.signers.bits.testn(index)
+    );
+  }
+
+  function sleep(ms) {
+    return new Promise((resolve) => setTimeout(resolve, ms));
+  }
+
+  // Asserts that the promise is rejected with the given program error message.
+  async function assertProgramError(promise, msg) {
+    try {
+      await promise;
+    } catch (err) {
+      assert.strictEqual(err.msg, msg);
+      return;
+    }
+    assert.fail(`Expected the program to fail with: ${msg}`);
+  }
+
   it("Tests the multisig program", async () => {
 
     // Generate a new random keypair
@@ -20,7 +262,7 @@ describe("multisig", () => {
       [multisig.publicKey.toBuffer()],
       program.programId
     );
-    const multisigSize = 200; // Big enough.
+    const multisigSize = 1000; // Big enough.
 
     // set the owners of the wallet
     const ownerA = anchor.web3.Keypair.generate();
@@ -42,19 +284,30 @@ describe("multisig", () => {
     // passing in two accounts for the context: 
     //  1. the multisig PDA and 
     //  2. the programs rent pubkey
-    await program.rpc.createMultisig(description, owners, threshold, nonce, {
-      accounts: {
-        multisig: multisig.publicKey,
-        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+    await program.rpc.createMultisig(
+      description,
+      owners,
+      threshold,
+      nonce,
+      {
+        strict: false,
+        emergencyRecipient: anchor.web3.PublicKey.default,
+        weights: [],
       },
-      instructions: [
-        await program.account.multisig.createInstruction(
-          multisig,
-          multisigSize
-        ),
-      ],
-      signers: [multisig],
-    });
+      {
+        accounts: {
+          multisig: multisig.publicKey,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        },
+        instructions: [
+          await program.account.multisig.createInstruction(
+            multisig,
+            multisigSize
+          ),
+        ],
+        signers: [multisig],
+      }
+    );
 
     // run initial create multisig account tests
     let multisigAccount = await program.account.multisig.fetch(multisig.publicKey);
@@ -90,6 +343,7 @@ describe("multisig", () => {
     // we encode the name of the isntruction, as well as the function parameters to be passed in
     const data = program.coder.instruction.encode("set_owners", {
       owners: newOwners,
+      weights: [],
     });
     // program.coder: provides a facade for encoding and decoding all IDL related objects
     // program.coder.instruction: An obj that encodes and decodes program instructions
@@ -112,7 +366,7 @@ describe("multisig", () => {
     //    4. the programs rent pubkey
     //  Instructions: A list of instructions to carry out within this transaction, here we initialize a single createInstruction to create a Transaction data account
     //  Signers: the transaction account itself and the transaction proposer
-    await program.rpc.createTransaction(pid, accounts, data, {
+    await program.rpc.createTransaction(pid, accounts, data, null, {
       accounts: {
         multisig: multisig.publicKey,
         transaction: transaction.publicKey,
@@ -152,12 +406,13 @@ describe("multisig", () => {
 
     // TODO: Document
     // Now that we've reached the threshold, send the transaction.
-    await program.rpc.executeTransaction({
+    await program.rpc.executeTransaction(false, null, {
       // Set the accounts to be used in the context
       accounts: {
         multisig: multisig.publicKey,
         multisigSigner,
         transaction: transaction.publicKey,
+        executor: program.provider.wallet.publicKey,
       },
       // Set the remaining accounts that wont be intially deserialized
       remainingAccounts: program.instruction.setOwners
@@ -187,4 +442,5465 @@ describe("multisig", () => {
     assert.deepStrictEqual(multisigAccount.owners, newOwners);
     assert.ok(multisigAccount.ownerSetSeqno === 1);
   });
+
+  it("Rejects a transaction account too small for the proposal", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const { multisig } = await createMultisig([ownerA.publicKey], 1);
+
+    // 64 bytes of data alone don't fit in a 100 byte account.
+    await assertProgramError(
+      createTransaction(
+        multisig,
+        ownerA,
+        program.programId,
+        [],
+        Buffer.alloc(64, 1),
+        100
+      ),
+      "The transaction account is too small to hold the proposal."
+    );
+  });
+
+  it("Freezes and unfreezes a transaction", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      1
+    );
+
+    // The proposal under review.
+    const accounts = authAccounts(multisig, multisigSigner);
+    const target = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("change_threshold", {
+        threshold: new anchor.BN(2),
+      })
+    );
+    const targetMeta = [
+      { pubkey: target.publicKey, isWritable: true, isSigner: false },
+    ];
+    const freezeAccounts = [
+      { pubkey: multisig.publicKey, isWritable: false, isSigner: false },
+      { pubkey: multisigSigner, isWritable: false, isSigner: true },
+    ].concat(targetMeta);
+
+    const freeze = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      freezeAccounts,
+      program.coder.instruction.encode("freeze_transaction", {})
+    );
+    await executeTransaction(multisig, multisigSigner, freeze, freezeAccounts);
+    assert.ok((await program.account.transaction.fetch(target.publicKey)).frozen);
+
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, target, accounts),
+      "The given transaction is frozen."
+    );
+
+    const unfreeze = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      freezeAccounts,
+      program.coder.instruction.encode("unfreeze_transaction", {})
+    );
+    await executeTransaction(multisig, multisigSigner, unfreeze, freezeAccounts);
+
+    await executeTransaction(multisig, multisigSigner, target, accounts);
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.ok(multisigAccount.threshold.eq(new anchor.BN(2)));
+  });
+
+  it("Blocks the latest approver from executing during the cooldown", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      1
+    );
+    await assertProgramError(
+      govern(multisig, multisigSigner, ownerA, "set_approve_execute_cooldown", {
+        cooldown: new anchor.BN(-1),
+      }),
+      "The timelock can't be negative."
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_approve_execute_cooldown", {
+      cooldown: new anchor.BN(3600),
+    });
+    await govern(multisig, multisigSigner, ownerA, "change_threshold", {
+      threshold: new anchor.BN(2),
+    });
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("change_threshold", {
+        threshold: new anchor.BN(1),
+      })
+    );
+    await approve(multisig, transaction, ownerB);
+
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts, {
+        executor: ownerB,
+      }),
+      "The latest approver can't execute the transaction during the cooldown."
+    );
+    await executeTransaction(multisig, multisigSigner, transaction, accounts, {
+      executor: ownerA,
+    });
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.ok(multisigAccount.threshold.eq(new anchor.BN(1)));
+  });
+
+  it("Advances a durable nonce before executing the proposal", async () => {
+    const provider = program.provider;
+    const ownerA = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey],
+      1
+    );
+
+    const nonceAccount = anchor.web3.Keypair.generate();
+    await provider.send(
+      new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.createNonceAccount({
+          fromPubkey: provider.wallet.publicKey,
+          noncePubkey: nonceAccount.publicKey,
+          authorizedPubkey: multisigSigner,
+          lamports: await provider.connection.getMinimumBalanceForRentExemption(
+            anchor.web3.NONCE_ACCOUNT_LENGTH
+          ),
+        })
+      ),
+      [nonceAccount]
+    );
+    const before = await provider.connection.getNonce(nonceAccount.publicKey);
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = anchor.web3.Keypair.generate();
+    await program.rpc.createDurableTransaction(
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("change_threshold", {
+        threshold: new anchor.BN(1),
+      }),
+      {
+        accounts: {
+          multisig: multisig.publicKey,
+          multisigSigner,
+          transaction: transaction.publicKey,
+          proposer: ownerA.publicKey,
+          nonceAccount: nonceAccount.publicKey,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        },
+        instructions: [
+          await program.account.transaction.createInstruction(transaction, 1000),
+        ],
+        signers: [transaction, ownerA],
+      }
+    );
+    const txAccount = await program.account.transaction.fetch(
+      transaction.publicKey
+    );
+    assert.ok(txAccount.durableNonce.equals(nonceAccount.publicKey));
+
+    await executeTransaction(multisig, multisigSigner, transaction, accounts, {
+      extraAccounts: [
+        { pubkey: nonceAccount.publicKey, isWritable: true, isSigner: false },
+        {
+          pubkey: anchor.web3.SYSVAR_RECENT_BLOCKHASHES_PUBKEY,
+          isWritable: false,
+          isSigner: false,
+        },
+        {
+          pubkey: anchor.web3.SystemProgram.programId,
+          isWritable: false,
+          isSigner: false,
+        },
+      ],
+    });
+    const after = await provider.connection.getNonce(nonceAccount.publicKey);
+    assert.notStrictEqual(after.nonce, before.nonce);
+  });
+
+  it("Pauses and unpauses with asymmetric quorums", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_pause_thresholds", {
+      pauseThreshold: new anchor.BN(1),
+      unpauseThreshold: new anchor.BN(3),
+    });
+    await govern(multisig, multisigSigner, ownerA, "change_threshold", {
+      threshold: new anchor.BN(2),
+    });
+
+    // A single owner can pause.
+    const accounts = authAccounts(multisig, multisigSigner);
+    const setPause = (paused) =>
+      createTransaction(
+        multisig,
+        ownerA,
+        program.programId,
+        accounts,
+        program.coder.instruction.encode("set_pause", { paused })
+      );
+    await executeTransaction(
+      multisig,
+      multisigSigner,
+      await setPause(true),
+      accounts
+    );
+    assert.ok((await program.account.multisig.fetch(multisig.publicKey)).paused);
+
+    // Nothing else runs while paused, even with the regular threshold met.
+    const other = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("change_threshold", {
+        threshold: new anchor.BN(1),
+      })
+    );
+    await approve(multisig, other, ownerB);
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, other, accounts),
+      "The multisig is paused."
+    );
+
+    // Unpausing needs all three owners.
+    const unpause = await setPause(false);
+    await approve(multisig, unpause, ownerB);
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, unpause, accounts),
+      "Not enough owners signed this transaction."
+    );
+    await approve(multisig, unpause, ownerC);
+    await executeTransaction(multisig, multisigSigner, unpause, accounts);
+
+    await executeTransaction(multisig, multisigSigner, other, accounts);
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.ok(!multisigAccount.paused);
+    assert.ok(multisigAccount.threshold.eq(new anchor.BN(1)));
+  });
+
+  it("Validates a transaction's integrity against its multisig", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      1
+    );
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      authAccounts(multisig, multisigSigner),
+      program.coder.instruction.encode("change_threshold", {
+        threshold: new anchor.BN(1),
+      })
+    );
+    const validate = () =>
+      program.rpc.validateTransactionIntegrity({
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: transaction.publicKey,
+        },
+      });
+    await validate();
+
+    // Growing the owner set leaves the transaction's signers out of line.
+    await govern(multisig, multisigSigner, ownerA, "set_owners", {
+      owners: [
+        ownerA.publicKey,
+        ownerB.publicKey,
+        ownerC.publicKey,
+        anchor.web3.Keypair.generate().publicKey,
+      ],
+      weights: [],
+    });
+    await assertProgramError(
+      validate(),
+      "The transaction's signers are inconsistent with the multisig."
+    );
+  });
+
+  it("Stores a content hash and executes untampered proposals", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_invalidate_on_tamper", {
+      invalidate: true,
+    });
+    assert.ok(
+      (await program.account.multisig.fetch(multisig.publicKey))
+        .invalidateOnTamper
+    );
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const data = program.coder.instruction.encode("change_threshold", {
+      threshold: new anchor.BN(1),
+    });
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      data
+    );
+    let txAccount = await program.account.transaction.fetch(
+      transaction.publicKey
+    );
+    assert.deepStrictEqual(
+      Buffer.from(txAccount.contentHash),
+      contentHash(program.programId, accounts, data)
+    );
+
+    // Matching content executes normally and keeps its approvals.
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+    txAccount = await program.account.transaction.fetch(transaction.publicKey);
+    assert.ok(txAccount.didExecute);
+    assert.strictEqual(txAccount.tamperSeqno, 0);
+    assert.deepStrictEqual(signedBy(txAccount), [true]);
+  });
+
+  it("Returns a serialized snapshot of a proposal", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      2
+    );
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      authAccounts(multisig, multisigSigner),
+      program.coder.instruction.encode("change_threshold", {
+        threshold: new anchor.BN(1),
+      })
+    );
+
+    const simulation = await simulate(
+      program.instruction.serializeProposal({
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: transaction.publicKey,
+        },
+      }),
+      1024
+    );
+    const snapshot = program.coder.types.decode(
+      "ProposalSnapshot",
+      simulation.returnData
+    );
+    const txAccount = await program.account.transaction.fetch(
+      transaction.publicKey
+    );
+    assert.ok(snapshot.programId.equals(txAccount.programId));
+    assert.deepStrictEqual(snapshot.accounts, txAccount.accounts);
+    assert.deepStrictEqual(Buffer.from(snapshot.data), txAccount.data);
+    assert.ok(snapshot.signers.bits.eq(txAccount.signers.bits));
+    assert.ok(snapshot.threshold.eq(new anchor.BN(2)));
+  });
+
+  it("Only allows raising the threshold in alert mode", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      1
+    );
+
+    // Any single owner can raise the alert.
+    await program.rpc.setAlertMode({
+      accounts: { multisig: multisig.publicKey, owner: ownerB.publicKey },
+      signers: [ownerB],
+    });
+
+    await govern(multisig, multisigSigner, ownerA, "change_threshold", {
+      threshold: new anchor.BN(2),
+    });
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const lower = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("change_threshold", {
+        threshold: new anchor.BN(1),
+      })
+    );
+    await approve(multisig, lower, ownerB);
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, lower, accounts),
+      "The threshold can't be lowered while in alert mode."
+    );
+    let multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.ok(multisigAccount.alertMode);
+    assert.ok(multisigAccount.threshold.eq(new anchor.BN(2)));
+
+    // But no single owner can stand it down, only the multisig signer can.
+    await assert.rejects(
+      program.rpc.clearAlertMode({
+        accounts: { multisig: multisig.publicKey, multisigSigner },
+        signers: [ownerC],
+      }),
+      "only the multisig signer can turn alert mode off"
+    );
+    multisigAccount = await program.account.multisig.fetch(multisig.publicKey);
+    assert.ok(multisigAccount.alertMode);
+
+    const clear = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("clear_alert_mode", {})
+    );
+    await approve(multisig, clear, ownerB);
+    await executeTransaction(multisig, multisigSigner, clear, accounts);
+    multisigAccount = await program.account.multisig.fetch(multisig.publicKey);
+    assert.ok(!multisigAccount.alertMode);
+  });
+
+  it("Creates transactions at deterministic program addresses", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey],
+      1
+    );
+    // Fund the proposer, who pays for the transaction account.
+    await program.provider.connection.confirmTransaction(
+      await program.provider.connection.requestAirdrop(
+        ownerA.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+
+    const index = new anchor.BN(0);
+    const [txAddress, bump] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("tx"),
+        multisig.publicKey.toBuffer(),
+        index.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    await program.rpc.createTransactionPda(
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("change_threshold", {
+        threshold: new anchor.BN(1),
+      }),
+      index,
+      bump,
+      {
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: txAddress,
+          proposer: ownerA.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        },
+        signers: [ownerA],
+      }
+    );
+
+    const txAccount = await program.account.transaction.fetch(txAddress);
+    assert.strictEqual(txAccount.bump, bump);
+    assert.ok(txAccount.multisig.equals(multisig.publicKey));
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.ok(multisigAccount.transactionCount.eq(new anchor.BN(1)));
+  });
+
+  it("Withholds execution until the slot delay has passed", async () => {
+    const connection = program.provider.connection;
+    const ownerA = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey],
+      1
+    );
+    const delay = 8;
+    await govern(multisig, multisigSigner, ownerA, "set_min_slot_delay", {
+      minSlotDelay: new anchor.BN(delay),
+    });
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("change_threshold", {
+        threshold: new anchor.BN(1),
+      })
+    );
+    const txAccount = await program.account.transaction.fetch(
+      transaction.publicKey
+    );
+    const approvedSlot = txAccount.approvedSlot.toNumber();
+    assert.ok(approvedSlot > 0);
+
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts),
+      "Not enough slots have passed since the transaction was approved."
+    );
+
+    while ((await connection.getSlot()) < approvedSlot + delay) {
+      await sleep(200);
+    }
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+    assert.ok(
+      (await program.account.transaction.fetch(transaction.publicKey))
+        .didExecute
+    );
+  });
+
+  it("Proposes mints within the multisig's mint cap", async () => {
+    const provider = program.provider;
+    const ownerA = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_max_mint_amount", {
+      maxMintAmount: new anchor.BN(1000),
+    });
+
+    const mint = await Token.createMint(
+      provider.connection,
+      provider.wallet.payer,
+      multisigSigner,
+      null,
+      0,
+      TOKEN_PROGRAM_ID
+    );
+    const destination = await mint.createAccount(provider.wallet.publicKey);
+
+    const proposeMintTo = async (amount) => {
+      const transaction = anchor.web3.Keypair.generate();
+      await program.rpc.proposeMintTo(
+        mint.publicKey,
+        destination,
+        new anchor.BN(amount),
+        {
+          accounts: {
+            multisig: multisig.publicKey,
+            multisigSigner,
+            transaction: transaction.publicKey,
+            proposer: ownerA.publicKey,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          },
+          instructions: [
+            await program.account.transaction.createInstruction(
+              transaction,
+              1000
+            ),
+          ],
+          signers: [transaction, ownerA],
+        }
+      );
+      return transaction;
+    };
+
+    const transaction = await proposeMintTo(500);
+    const txAccount = await program.account.transaction.fetch(
+      transaction.publicKey
+    );
+    await executeTransaction(
+      multisig,
+      multisigSigner,
+      transaction,
+      txAccount.accounts,
+      { pid: TOKEN_PROGRAM_ID }
+    );
+    assert.strictEqual(
+      (await mint.getAccountInfo(destination)).amount.toNumber(),
+      500
+    );
+
+    await assertProgramError(
+      proposeMintTo(5000),
+      "The amount exceeds the multisig's mint cap."
+    );
+  });
+
+  it("Weighs approvals and optionally expires them on reweighting", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_weight", {
+      owner: ownerA.publicKey,
+      weight: new anchor.BN(3),
+    });
+    await govern(multisig, multisigSigner, ownerA, "change_threshold", {
+      threshold: new anchor.BN(3),
+    });
+
+    // Owner B alone doesn't carry enough weight.
+    const accounts = authAccounts(multisig, multisigSigner);
+    const data = program.coder.instruction.encode("set_min_slot_delay", {
+      minSlotDelay: new anchor.BN(0),
+    });
+    const transaction = await createTransaction(
+      multisig,
+      ownerB,
+      program.programId,
+      accounts,
+      data
+    );
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts),
+      "Not enough owners signed this transaction."
+    );
+
+    // Reweighting leaves pending approvals alone by default.
+    await govern(multisig, multisigSigner, ownerA, "set_weight", {
+      owner: ownerB.publicKey,
+      weight: new anchor.BN(2),
+    });
+    await approve(multisig, transaction, ownerA);
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+
+    // Once enabled, reweighting invalidates pending transactions.
+    await govern(multisig, multisigSigner, ownerA, "set_reweight_invalidates", {
+      invalidates: true,
+    });
+    const pending = await createTransaction(
+      multisig,
+      ownerB,
+      program.programId,
+      accounts,
+      data
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_weight", {
+      owner: ownerB.publicKey,
+      weight: new anchor.BN(1),
+    });
+    await assertProgramError(
+      approve(multisig, pending, ownerA),
+      "The owner set changed since the transaction was created."
+    );
+
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.deepStrictEqual(
+      multisigAccount.weights.map((w) => w.toNumber()),
+      [3, 1]
+    );
+    assert.strictEqual(multisigAccount.ownerSetSeqno, 1);
+  });
+
+  it("Logs compute units around the CPI when profiling", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey],
+      1
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const data = program.coder.instruction.encode("set_min_slot_delay", {
+      minSlotDelay: new anchor.BN(0),
+    });
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      data
+    );
+
+    const simulate = (profile) =>
+      program.simulate.executeTransaction(profile, null, {
+        accounts: {
+          multisig: multisig.publicKey,
+          multisigSigner,
+          transaction: transaction.publicKey,
+          executor: program.provider.wallet.publicKey,
+        },
+        remainingAccounts: accounts
+          .map((meta) =>
+            meta.pubkey.equals(multisigSigner)
+              ? { ...meta, isSigner: false }
+              : meta
+          )
+          .concat({
+            pubkey: program.programId,
+            isWritable: false,
+            isSigner: false,
+          }),
+      });
+    const computeLogs = (simulation) =>
+      simulation.raw.filter((log) =>
+        /^Program consumption: \d+ units remaining$/.test(log)
+      );
+
+    assert.strictEqual(computeLogs(await simulate(false)).length, 0);
+    assert.strictEqual(computeLogs(await simulate(true)).length, 2);
+  });
+
+  it("Requires approvals to span the minimum number of owner groups", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      2
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const setGroups = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("set_owner_groups", {
+        ownerGroups: Buffer.from([0, 0, 1]),
+        minGroups: 2,
+      })
+    );
+    await approve(multisig, setGroups, ownerB);
+    await executeTransaction(multisig, multisigSigner, setGroups, accounts);
+
+    const data = program.coder.instruction.encode("set_min_slot_delay", {
+      minSlotDelay: new anchor.BN(0),
+    });
+
+    // Owners A and B meet the threshold, but both are in group 0.
+    const sameGroup = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      data
+    );
+    await approve(multisig, sameGroup, ownerB);
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, sameGroup, accounts),
+      "The approvals don't span enough owner groups."
+    );
+
+    // Owner C brings in group 1.
+    const acrossGroups = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      data
+    );
+    await approve(multisig, acrossGroups, ownerC);
+    await executeTransaction(multisig, multisigSigner, acrossGroups, accounts);
+    assert.ok(
+      (await program.account.transaction.fetch(acrossGroups.publicKey))
+        .didExecute
+    );
+  });
+
+  it("Expires transactions from creation or from their first approval", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const accounts = (multisig, multisigSigner) =>
+      authAccounts(multisig, multisigSigner);
+    const data = program.coder.instruction.encode("set_min_slot_delay", {
+      minSlotDelay: new anchor.BN(0),
+    });
+
+    // Sets up a 2-of-2 multisig whose transactions live for two seconds.
+    const setup = async (expiryFrom) => {
+      const { multisig, multisigSigner } = await createMultisig(
+        [ownerA.publicKey, ownerB.publicKey],
+        1
+      );
+      await govern(multisig, multisigSigner, ownerA, "set_transaction_lifetime", {
+        lifetime: new anchor.BN(2),
+        expiryFrom,
+      });
+      await govern(multisig, multisigSigner, ownerA, "change_threshold", {
+        threshold: new anchor.BN(2),
+      });
+      const transaction = await createTransaction(
+        multisig,
+        ownerA,
+        program.programId,
+        accounts(multisig, multisigSigner),
+        data
+      );
+      return { multisig, multisigSigner, transaction };
+    };
+
+    // Measured from creation, sitting in draft uses up the lifetime.
+    let { multisig, multisigSigner, transaction } = await setup(0);
+    await sleep(3000);
+    await approve(multisig, transaction, ownerB);
+    await assertProgramError(
+      executeTransaction(
+        multisig,
+        multisigSigner,
+        transaction,
+        accounts(multisig, multisigSigner)
+      ),
+      "The transaction has expired."
+    );
+
+    // Measured from the first approval, the clock starts when owner B signs.
+    ({ multisig, multisigSigner, transaction } = await setup(1));
+    await sleep(3000);
+    await approve(multisig, transaction, ownerB);
+    await executeTransaction(
+      multisig,
+      multisigSigner,
+      transaction,
+      accounts(multisig, multisigSigner)
+    );
+    const txAccount = await program.account.transaction.fetch(
+      transaction.publicKey
+    );
+    assert.ok(txAccount.didExecute);
+    assert.ok(txAccount.firstApprovedAt.gt(txAccount.createdAt));
+
+    // And it expires the lifetime after that approval.
+    const pending = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts(multisig, multisigSigner),
+      data
+    );
+    await approve(multisig, pending, ownerB);
+    await sleep(3000);
+    await assertProgramError(
+      executeTransaction(
+        multisig,
+        multisigSigner,
+        pending,
+        accounts(multisig, multisigSigner)
+      ),
+      "The transaction has expired."
+    );
+  });
+
+  it("Reports which of several multisigs a candidate owns", async () => {
+    const candidate = anchor.web3.Keypair.generate();
+    const other = anchor.web3.Keypair.generate();
+    const { multisig: first } = await createMultisig(
+      [candidate.publicKey, other.publicKey],
+      1
+    );
+    const { multisig: second } = await createMultisig([other.publicKey], 1);
+    const { multisig: third } = await createMultisig(
+      [other.publicKey, candidate.publicKey],
+      2
+    );
+
+    const simulation = await program.simulate.isOwnerMulti(
+      candidate.publicKey,
+      {
+        accounts: {},
+        remainingAccounts: [first, second, third].map((multisig) => ({
+          pubkey: multisig.publicKey,
+          isWritable: false,
+          isSigner: false,
+        })),
+      }
+    );
+    const logs = simulation.raw;
+    assert.ok(logs.includes(`Program log: ${first.publicKey}: owner`));
+    assert.ok(logs.includes(`Program log: ${second.publicKey}: not owner`));
+    assert.ok(logs.includes(`Program log: ${third.publicKey}: owner`));
+  });
+
+  it("Hands execution authority over once the new executor accepts", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const executorA = anchor.web3.Keypair.generate();
+    const executorB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey],
+      1
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const acceptExecutor = (executor) =>
+      program.rpc.acceptExecutor({
+        accounts: {
+          multisig: multisig.publicKey,
+          executor: executor.publicKey,
+        },
+        signers: [executor],
+      });
+    const proposeExecutor = async (executor, timeout, by) => {
+      const transaction = await createTransaction(
+        multisig,
+        ownerA,
+        program.programId,
+        accounts,
+        program.coder.instruction.encode("propose_executor", {
+          executor: executor.publicKey,
+          timeout: new anchor.BN(timeout),
+        })
+      );
+      await executeTransaction(multisig, multisigSigner, transaction, accounts, {
+        executor: by,
+      });
+    };
+
+    // Propose and accept executor A.
+    await proposeExecutor(executorA, 0);
+    let multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.strictEqual(multisigAccount.executor, null);
+    assert.ok(multisigAccount.pendingExecutor.equals(executorA.publicKey));
+    await assertProgramError(
+      acceptExecutor(executorB),
+      "The signer isn't the pending executor."
+    );
+    await acceptExecutor(executorA);
+    multisigAccount = await program.account.multisig.fetch(multisig.publicKey);
+    assert.ok(multisigAccount.executor.equals(executorA.publicKey));
+    assert.strictEqual(multisigAccount.pendingExecutor, null);
+
+    // Now only executor A may execute.
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("set_min_slot_delay", {
+        minSlotDelay: new anchor.BN(0),
+      })
+    );
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts),
+      "Only the multisig's executor can execute transactions."
+    );
+
+    // A handoff to executor B that times out leaves executor A in place.
+    await proposeExecutor(executorB, 1, executorA);
+    await sleep(3000);
+    await assertProgramError(
+      acceptExecutor(executorB),
+      "The executor handoff has expired."
+    );
+    await executeTransaction(multisig, multisigSigner, transaction, accounts, {
+      executor: executorA,
+    });
+    multisigAccount = await program.account.multisig.fetch(multisig.publicKey);
+    assert.ok(multisigAccount.executor.equals(executorA.publicKey));
+  });
+
+  it("Deposits into escrow and proposes a withdrawal at once", async () => {
+    const provider = program.provider;
+    const depositor = provider.wallet.publicKey;
+    const { multisig, multisigSigner } = await createMultisig([depositor], 1);
+    const destination = anchor.web3.Keypair.generate().publicKey;
+    const transaction = anchor.web3.Keypair.generate();
+    const balanceBefore = await provider.connection.getBalance(
+      multisig.publicKey
+    );
+
+    await program.rpc.depositAndProposeWithdrawal(
+      new anchor.BN(2000000),
+      new anchor.BN(1500000),
+      destination,
+      noReference,
+      {
+        accounts: {
+          multisig: multisig.publicKey,
+          multisigSigner,
+          transaction: transaction.publicKey,
+          depositor,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        },
+        instructions: [
+          await program.account.transaction.createInstruction(transaction, 1000),
+        ],
+        signers: [transaction],
+      }
+    );
+
+    assert.strictEqual(
+      await provider.connection.getBalance(multisig.publicKey),
+      balanceBefore + 2000000
+    );
+    assert.ok(
+      (await program.account.multisig.fetch(multisig.publicKey)).lamports.eq(
+        new anchor.BN(2000000)
+      )
+    );
+    const txAccount = await program.account.transaction.fetch(
+      transaction.publicKey
+    );
+    assert.ok(txAccount.programId.equals(program.programId));
+    assert.ok(txAccount.accounts[2].pubkey.equals(destination));
+    assert.strictEqual(txAccount.didExecute, false);
+
+    await executeTransaction(
+      multisig,
+      multisigSigner,
+      transaction,
+      txAccount.accounts
+    );
+    assert.strictEqual(await provider.connection.getBalance(destination), 1500000);
+    assert.ok(
+      (await program.account.multisig.fetch(multisig.publicKey)).lamports.eq(
+        new anchor.BN(500000)
+      )
+    );
+  });
+
+  it("Logs who signed a transaction when executing it", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      2
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("set_min_slot_delay", {
+        minSlotDelay: new anchor.BN(0),
+      })
+    );
+    await approve(multisig, transaction, ownerC);
+
+    const simulation = await program.simulate.executeTransaction(false, null, {
+      accounts: {
+        multisig: multisig.publicKey,
+        multisigSigner,
+        transaction: transaction.publicKey,
+        executor: program.provider.wallet.publicKey,
+      },
+      remainingAccounts: accounts
+        .map((meta) =>
+          meta.pubkey.equals(multisigSigner) ? { ...meta, isSigner: false } : meta
+        )
+        .concat({
+          pubkey: program.programId,
+          isWritable: false,
+          isSigner: false,
+        }),
+    });
+    const line = simulation.raw.find((log) =>
+      log.startsWith("Program log: signers: ")
+    );
+    const [, signers, threshold, weighted] = line.match(
+      /^Program log: signers: (\S*) threshold: (\d+) weighted: (\w+)$/
+    );
+    assert.deepStrictEqual(signers.split(","), [
+      `0:${ownerA.publicKey}`,
+      `2:${ownerC.publicKey}`,
+    ]);
+    assert.strictEqual(threshold, "2");
+    assert.strictEqual(weighted, "false");
+  });
+
+  it("Absorbs a single-sig mint authority into the multisig", async () => {
+    const provider = program.provider;
+    const ownerA = anchor.web3.Keypair.generate();
+    const mintAuthority = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey],
+      1
+    );
+    const mint = await Token.createMint(
+      provider.connection,
+      provider.wallet.payer,
+      mintAuthority.publicKey,
+      null,
+      0,
+      TOKEN_PROGRAM_ID
+    );
+
+    // The new authority is overwritten with the multisig signer on chain.
+    const ix = Token.createSetAuthorityInstruction(
+      TOKEN_PROGRAM_ID,
+      mint.publicKey,
+      mintAuthority.publicKey,
+      "MintTokens",
+      mintAuthority.publicKey,
+      []
+    );
+    const transaction = anchor.web3.Keypair.generate();
+    await program.rpc.proposeAbsorbAuthority(
+      ix.programId,
+      ix.keys,
+      ix.data,
+      3,
+      {
+        accounts: {
+          multisig: multisig.publicKey,
+          multisigSigner,
+          transaction: transaction.publicKey,
+          proposer: ownerA.publicKey,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        },
+        instructions: [
+          await program.account.transaction.createInstruction(transaction, 1000),
+        ],
+        signers: [transaction, ownerA],
+      }
+    );
+
+    await executeTransaction(multisig, multisigSigner, transaction, ix.keys, {
+      pid: TOKEN_PROGRAM_ID,
+      signers: [mintAuthority],
+    });
+    const mintInfo = await mint.getMintInfo();
+    assert.ok(mintInfo.mintAuthority.equals(multisigSigner));
+  });
+
+  it("Clamps the threshold to the active owners once enabled", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      1
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    await govern(multisig, multisigSigner, ownerA, "set_clamp_threshold_to_active", {
+      enabled: true,
+    });
+    await govern(multisig, multisigSigner, ownerA, "change_threshold", {
+      threshold: new anchor.BN(2),
+    });
+    const suspend = async (owner) => {
+      const transaction = await createTransaction(
+        multisig,
+        ownerA,
+        program.programId,
+        accounts,
+        program.coder.instruction.encode("set_owner_suspended", {
+          owner: owner.publicKey,
+          suspended: true,
+        })
+      );
+      await approve(multisig, transaction, ownerB);
+      await executeTransaction(multisig, multisigSigner, transaction, accounts);
+    };
+
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("set_min_slot_delay", {
+        minSlotDelay: new anchor.BN(0),
+      })
+    );
+
+    // Two owners are still active, so the threshold of two stands.
+    await suspend(ownerC);
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts),
+      "Not enough owners signed this transaction."
+    );
+
+    // With owner A alone active, its approval is enough.
+    await suspend(ownerB);
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.ok(multisigAccount.threshold.eq(new anchor.BN(2)));
+    assert.strictEqual(multisigAccount.suspendedOwners.length, 2);
+  });
+
+  it("Records config changes in a config history", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey],
+      1
+    );
+    const history = anchor.web3.Keypair.generate();
+    await program.rpc.createConfigHistory({
+      accounts: {
+        multisig: multisig.publicKey,
+        history: history.publicKey,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      },
+      instructions: [
+        await program.account.configHistory.createInstruction(history, 1000),
+      ],
+      signers: [history],
+    });
+    const extra = [
+      { pubkey: history.publicKey, isWritable: true, isSigner: false },
+    ];
+
+    const owners = [ownerA.publicKey, ownerB.publicKey];
+    await govern(multisig, multisigSigner, ownerA, "set_owners", { owners, weights: [] }, extra);
+    await govern(
+      multisig,
+      multisigSigner,
+      ownerA,
+      "change_threshold",
+      { threshold: new anchor.BN(2) },
+      extra
+    );
+    // Without the history account, changes aren't recorded.
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      authAccounts(multisig, multisigSigner),
+      program.coder.instruction.encode("rename", { description: "renamed" })
+    );
+    await approve(multisig, transaction, ownerB);
+    await executeTransaction(
+      multisig,
+      multisigSigner,
+      transaction,
+      authAccounts(multisig, multisigSigner)
+    );
+
+    const { entries } = await program.account.configHistory.fetch(
+      history.publicKey
+    );
+    assert.strictEqual(entries.length, 2);
+    assert.deepStrictEqual(
+      entries.map((entry) => entry.seqno.toNumber()),
+      [1, 2]
+    );
+    assert.deepStrictEqual(
+      entries.map((entry) => entry.threshold.toNumber()),
+      [1, 2]
+    );
+    const ownersHash = crypto
+      .createHash("sha256")
+      .update(Buffer.concat(owners.map((owner) => owner.toBuffer())))
+      .digest();
+    assert.deepStrictEqual(Buffer.from(entries[1].ownersHash), ownersHash);
+    assert.strictEqual(
+      (await program.account.multisig.fetch(multisig.publicKey)).description,
+      "renamed"
+    );
+  });
+
+  it("Rejects withdrawals that would breach the escrow's reserve", async () => {
+    const provider = program.provider;
+    const depositor = provider.wallet.payer;
+    const { multisig, multisigSigner } = await createMultisig(
+      [depositor.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, depositor, "set_min_reserve", {
+      minReserve: new anchor.BN(500000),
+    });
+    const destination = anchor.web3.Keypair.generate().publicKey;
+    const depositAndPropose = async (amountIn, amountOut) => {
+      const transaction = anchor.web3.Keypair.generate();
+      await program.rpc.depositAndProposeWithdrawal(
+        new anchor.BN(amountIn),
+        new anchor.BN(amountOut),
+        destination,
+        noReference,
+        {
+          accounts: {
+            multisig: multisig.publicKey,
+            multisigSigner,
+            transaction: transaction.publicKey,
+            depositor: depositor.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          },
+          instructions: [
+            await program.account.transaction.createInstruction(
+              transaction,
+              1000
+            ),
+          ],
+          signers: [transaction],
+        }
+      );
+      const txAccount = await program.account.transaction.fetch(
+        transaction.publicKey
+      );
+      return () =>
+        executeTransaction(
+          multisig,
+          multisigSigner,
+          transaction,
+          txAccount.accounts
+        );
+    };
+
+    // Withdrawing down to the reserve is fine.
+    const withdrawToReserve = await depositAndPropose(2000000, 1500000);
+    await withdrawToReserve();
+    // Going any lower isn't.
+    const withdrawBelowReserve = await depositAndPropose(0, 1);
+    await assertProgramError(
+      withdrawBelowReserve(),
+      "The withdrawal would leave the escrow below its reserve."
+    );
+    assert.ok(
+      (await program.account.multisig.fetch(multisig.publicKey)).lamports.eq(
+        new anchor.BN(500000)
+      )
+    );
+  });
+
+  it("Approves transactions across several multisigs at once", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const outsider = anchor.web3.Keypair.generate();
+    const proposals = [];
+    for (const owners of [
+      [ownerA.publicKey, ownerB.publicKey],
+      [ownerA.publicKey, ownerB.publicKey],
+      [ownerA.publicKey, outsider.publicKey],
+    ]) {
+      const { multisig, multisigSigner } = await createMultisig(owners, 2);
+      const transaction = await createTransaction(
+        multisig,
+        ownerA,
+        program.programId,
+        authAccounts(multisig, multisigSigner),
+        program.coder.instruction.encode("change_threshold", {
+          threshold: new anchor.BN(1),
+        })
+      );
+      proposals.push({ multisig, transaction });
+    }
+
+    await program.rpc.approveMulti({
+      accounts: { owner: ownerB.publicKey },
+      remainingAccounts: proposals.flatMap(({ multisig, transaction }) => [
+        { pubkey: multisig.publicKey, isWritable: false, isSigner: false },
+        { pubkey: transaction.publicKey, isWritable: true, isSigner: false },
+      ]),
+      signers: [ownerB],
+    });
+
+    const signers = [];
+    for (const { transaction } of proposals) {
+      signers.push(
+        signedBy(await program.account.transaction.fetch(transaction.publicKey))
+      );
+    }
+    assert.deepStrictEqual(signers, [
+      [true, true],
+      [true, true],
+      [true, false],
+    ]);
+  });
+
+  it("Rejects substituted accounts when a transaction's accounts are locked", async () => {
+    const owner = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    const other = await createMultisig([owner.publicKey], 1);
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = anchor.web3.Keypair.generate();
+    await program.rpc.createLockedTransaction(
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("set_min_slot_delay", {
+        minSlotDelay: new anchor.BN(0),
+      }),
+      {
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: transaction.publicKey,
+          proposer: owner.publicKey,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        },
+        instructions: [
+          await program.account.transaction.createInstruction(
+            transaction,
+            1000
+          ),
+        ],
+        signers: [transaction, owner],
+      }
+    );
+    assert.ok(
+      (await program.account.transaction.fetch(transaction.publicKey))
+        .accountsLocked
+    );
+
+    // Another multisig in place of the governed one.
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, [
+        { ...accounts[0], pubkey: other.multisig.publicKey },
+        accounts[1],
+      ]),
+      "The accounts passed don't match the locked transaction's accounts."
+    );
+    // The same accounts, reordered.
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, [
+        accounts[1],
+        accounts[0],
+      ]),
+      "The accounts passed don't match the locked transaction's accounts."
+    );
+    // An extra account tagged along.
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts, {
+        extraAccounts: [
+          {
+            pubkey: other.multisig.publicKey,
+            isWritable: false,
+            isSigner: false,
+          },
+        ],
+      }),
+      "The accounts passed don't match the locked transaction's accounts."
+    );
+
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+    assert.ok(
+      (await program.account.transaction.fetch(transaction.publicKey))
+        .didExecute
+    );
+  });
+
+  it("Emits events clients can decode from the program's IDL", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      2
+    );
+
+    const names = [
+      "TransactionCreated",
+      "TransactionApproved",
+      "TransactionExecuted",
+      "ConfigChanged",
+    ];
+    const events = {};
+    const listeners = names.map((name) => {
+      events[name] = [];
+      return program.addEventListener(name, (event) =>
+        events[name].push(event)
+      );
+    });
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("rename", { description: "evented" })
+    );
+    await approve(multisig, transaction, ownerB);
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+
+    // Logs reach the listeners asynchronously.
+    for (let i = 0; i < 20 && names.some((name) => !events[name].length); i++) {
+      await sleep(500);
+    }
+    for (const listener of listeners) {
+      await program.removeEventListener(listener);
+    }
+
+    const [created] = events.TransactionCreated;
+    assert.ok(created.multisig.equals(multisig.publicKey));
+    assert.ok(created.transaction.equals(transaction.publicKey));
+    assert.ok(created.proposer.equals(ownerA.publicKey));
+    assert.ok(created.programId.equals(program.programId));
+    assert.strictEqual(created.index.toNumber(), 0);
+
+    const [approved] = events.TransactionApproved;
+    assert.ok(approved.transaction.equals(transaction.publicKey));
+    assert.ok(approved.owner.equals(ownerB.publicKey));
+    assert.strictEqual(approved.approvalWeight.toNumber(), 2);
+    assert.strictEqual(approved.approvals.toNumber(), 2);
+    assert.strictEqual(approved.threshold.toNumber(), 2);
+
+    const [executed] = events.TransactionExecuted;
+    assert.ok(executed.transaction.equals(transaction.publicKey));
+    assert.ok(executed.executor.equals(program.provider.wallet.publicKey));
+
+    const [changed] = events.ConfigChanged;
+    assert.ok(changed.multisig.equals(multisig.publicKey));
+    assert.strictEqual(changed.description, "evented");
+    assert.strictEqual(changed.threshold.toNumber(), 2);
+    assert.deepStrictEqual(
+      changed.owners.map((owner) => owner.toString()),
+      [ownerA.publicKey.toString(), ownerB.publicKey.toString()]
+    );
+  });
+
+  it("Only lets owners execute once executor_must_be_owner is set", async () => {
+    const owner = anchor.web3.Keypair.generate();
+    const outsider = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    // While off, anyone can execute, e.g. the provider wallet govern uses.
+    await govern(multisig, multisigSigner, owner, "set_executor_must_be_owner", {
+      enabled: true,
+    });
+    assert.ok(
+      (await program.account.multisig.fetch(multisig.publicKey))
+        .executorMustBeOwner
+    );
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      owner,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("set_executor_must_be_owner", {
+        enabled: false,
+      })
+    );
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts, {
+        executor: outsider,
+      }),
+      "The given owner is not part of this multisig."
+    );
+    assert.ok(
+      (await program.account.transaction.fetch(transaction.publicKey)).executor.equals(
+        anchor.web3.PublicKey.default
+      )
+    );
+    await executeTransaction(multisig, multisigSigner, transaction, accounts, {
+      executor: owner,
+    });
+    assert.ok(
+      !(await program.account.multisig.fetch(multisig.publicKey))
+        .executorMustBeOwner
+    );
+    // The transaction records who executed it.
+    assert.ok(
+      (await program.account.transaction.fetch(transaction.publicKey)).executor.equals(
+        owner.publicKey
+      )
+    );
+  });
+
+  it("Computes the approval message owners sign off chain", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey],
+      1
+    );
+    // Bump the owner set seqno so it's part of what's checked.
+    await govern(multisig, multisigSigner, ownerA, "set_owners", {
+      owners: [ownerA.publicKey, ownerB.publicKey],
+      weights: [],
+    });
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      authAccounts(multisig, multisigSigner),
+      program.coder.instruction.encode("change_threshold", {
+        threshold: new anchor.BN(2),
+      })
+    );
+
+    const simulation = await simulate(
+      program.instruction.logApprovalMessage({
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: transaction.publicKey,
+        },
+      }),
+      32
+    );
+    const seqno = Buffer.alloc(4);
+    seqno.writeUInt32LE(1);
+    const expected = crypto
+      .createHash("sha256")
+      .update(
+        Buffer.concat([
+          Buffer.from("serum-multisig:approve:v1"),
+          multisig.publicKey.toBuffer(),
+          transaction.publicKey.toBuffer(),
+          seqno,
+        ])
+      )
+      .digest();
+    assert.deepStrictEqual(simulation.returnData, expected);
+    assert.ok(
+      simulation.raw.includes(
+        `Program log: approval message: ${expected.toString("base64")}`
+      )
+    );
+  });
+
+  it("Only approves a proposal matching the reviewed content hash", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      2
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const data = program.coder.instruction.encode("change_threshold", {
+      threshold: new anchor.BN(1),
+    });
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      data
+    );
+    const approveChecked = (expectedHash) =>
+      program.rpc.approveChecked([...expectedHash], {
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: transaction.publicKey,
+          owner: ownerB.publicKey,
+        },
+        signers: [ownerB],
+      });
+
+    // What the owner reviewed differs from what was proposed.
+    const reviewed = program.coder.instruction.encode("change_threshold", {
+      threshold: new anchor.BN(2),
+    });
+    await assertProgramError(
+      approveChecked(contentHash(program.programId, accounts, reviewed)),
+      "The transaction's content doesn't match its content hash."
+    );
+    assert.deepStrictEqual(
+      signedBy(await program.account.transaction.fetch(transaction.publicKey)),
+      [true, false]
+    );
+
+    await approveChecked(contentHash(program.programId, accounts, data));
+    assert.deepStrictEqual(
+      signedBy(await program.account.transaction.fetch(transaction.publicKey)),
+      [true, true]
+    );
+  });
+
+  it("Warns about or rejects thresholds below a majority", async () => {
+    const owners = [...Array(5)].map(
+      () => anchor.web3.Keypair.generate().publicKey
+    );
+    await assertProgramError(
+      createMultisig(owners, 2, 1000, true),
+      "The threshold is below a majority of the owners."
+    );
+    // A majority is fine either way.
+    await createMultisig(owners, 3, 1000, true);
+
+    // Without strict, a 1-of-5 is created with a warning.
+    const multisig = anchor.web3.Keypair.generate();
+    const [, nonce] = await anchor.web3.PublicKey.findProgramAddress(
+      [multisig.publicKey.toBuffer()],
+      program.programId
+    );
+    const simulation = await program.simulate.createMultisig(
+      "test multisig",
+      owners,
+      new anchor.BN(1),
+      nonce,
+      {
+        strict: false,
+        emergencyRecipient: anchor.web3.PublicKey.default,
+        weights: [],
+      },
+      {
+        accounts: {
+          multisig: multisig.publicKey,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        },
+        instructions: [
+          await program.account.multisig.createInstruction(multisig, 1000),
+        ],
+        signers: [multisig],
+      }
+    );
+    assert.ok(
+      simulation.raw.includes(
+        "Program log: Warning: threshold 1 is below a majority of the " +
+          "owners' total weight 5 (3)"
+      )
+    );
+    const { multisig: created } = await createMultisig(owners, 1);
+    assert.ok(
+      (await program.account.multisig.fetch(created.publicKey)).threshold.eq(
+        new anchor.BN(1)
+      )
+    );
+  });
+
+  it("Transfers escrowed lamports between two multisigs", async () => {
+    const provider = program.provider;
+    const owner = provider.wallet.payer;
+    const parent = await createMultisig([owner.publicKey], 1);
+    const child = await createMultisig([owner.publicKey], 1);
+
+    // Fund the parent's escrow. The withdrawal proposed along is left alone.
+    const transaction = anchor.web3.Keypair.generate();
+    await program.rpc.depositAndProposeWithdrawal(
+      new anchor.BN(2000000),
+      new anchor.BN(0),
+      owner.publicKey,
+      noReference,
+      {
+        accounts: {
+          multisig: parent.multisig.publicKey,
+          multisigSigner: parent.multisigSigner,
+          transaction: transaction.publicKey,
+          depositor: owner.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        },
+        instructions: [
+          await program.account.transaction.createInstruction(
+            transaction,
+            1000
+          ),
+        ],
+        signers: [transaction],
+      }
+    );
+
+    const balance = (pubkey) => provider.connection.getBalance(pubkey);
+    const parentBalance = await balance(parent.multisig.publicKey);
+    const childBalance = await balance(child.multisig.publicKey);
+    await govern(
+      parent.multisig,
+      parent.multisigSigner,
+      owner,
+      "transfer_escrow",
+      { amount: new anchor.BN(1500000) },
+      [
+        {
+          pubkey: child.multisig.publicKey,
+          isWritable: true,
+          isSigner: false,
+        },
+      ]
+    );
+
+    assert.strictEqual(
+      await balance(parent.multisig.publicKey),
+      parentBalance - 1500000
+    );
+    assert.strictEqual(
+      await balance(child.multisig.publicKey),
+      childBalance + 1500000
+    );
+    const parentAccount = await program.account.multisig.fetch(
+      parent.multisig.publicKey
+    );
+    const childAccount = await program.account.multisig.fetch(
+      child.multisig.publicKey
+    );
+    assert.ok(parentAccount.lamports.eq(new anchor.BN(500000)));
+    assert.ok(childAccount.lamports.eq(new anchor.BN(1500000)));
+
+    // The destination has to be a multisig.
+    await assertProgramError(
+      govern(
+        parent.multisig,
+        parent.multisigSigner,
+        owner,
+        "transfer_escrow",
+        { amount: new anchor.BN(1) },
+        [{ pubkey: transaction.publicKey, isWritable: true, isSigner: false }]
+      ),
+      "8 byte discriminator did not match what was expected"
+    );
+  });
+
+  it("Summarizes approvals at execution instead of emitting each", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_emit_per_approval", {
+      enabled: false,
+    });
+    await govern(multisig, multisigSigner, ownerA, "change_threshold", {
+      threshold: new anchor.BN(2),
+    });
+
+    const approvals = [];
+    const summaries = [];
+    const listeners = [
+      program.addEventListener("TransactionApproved", (event) =>
+        approvals.push(event)
+      ),
+      program.addEventListener("ApprovalBatchSummary", (event) =>
+        summaries.push(event)
+      ),
+    ];
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("set_emit_per_approval", {
+        enabled: true,
+      })
+    );
+    await approve(multisig, transaction, ownerB);
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+
+    for (let i = 0; i < 20 && !summaries.length; i++) {
+      await sleep(500);
+    }
+    for (const listener of listeners) {
+      await program.removeEventListener(listener);
+    }
+
+    assert.strictEqual(approvals.length, 0);
+    assert.strictEqual(summaries.length, 1);
+    const [summary] = summaries;
+    assert.ok(summary.transaction.equals(transaction.publicKey));
+    assert.deepStrictEqual(
+      summary.signers.map((signer) => signer.toString()),
+      [ownerA.publicKey.toString(), ownerB.publicKey.toString()]
+    );
+    const txAccount = await program.account.transaction.fetch(
+      transaction.publicKey
+    );
+    assert.deepStrictEqual(
+      summary.approvedAt.map((at) => at.toNumber()),
+      txAccount.approvedAt.map((at) => at.toNumber())
+    );
+    assert.ok(summary.approvedAt.every((at) => at.toNumber() > 0));
+  });
+
+  it("Raises the threshold and adds a slot delay in one proposal", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      2
+    );
+    // Roughly 24 hours of 400ms slots.
+    const minDelay = new anchor.BN(216000);
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("harden_governance", {
+        newThreshold: new anchor.BN(3),
+        minDelay,
+      })
+    );
+    await approve(multisig, transaction, ownerB);
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.ok(multisigAccount.threshold.eq(new anchor.BN(3)));
+    assert.ok(multisigAccount.minSlotDelay.eq(minDelay));
+    assert.strictEqual(multisigAccount.ownerSetSeqno, 1);
+  });
+
+  it("Bars a transaction's proposer from executing it", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      1
+    );
+    await govern(
+      multisig,
+      multisigSigner,
+      ownerA,
+      "set_proposer_cannot_execute",
+      { enabled: true }
+    );
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("set_min_slot_delay", {
+        minSlotDelay: new anchor.BN(0),
+      })
+    );
+    assert.ok(
+      (await program.account.transaction.fetch(
+        transaction.publicKey
+      )).proposer.equals(ownerA.publicKey)
+    );
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts, {
+        executor: ownerA,
+      }),
+      "The transaction's proposer can't execute it."
+    );
+    await executeTransaction(multisig, multisigSigner, transaction, accounts, {
+      executor: ownerB,
+    });
+  });
+
+  it("Imports the approvals of an expired proposal re-created as is", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_transaction_lifetime", {
+      lifetime: new anchor.BN(5),
+      expiryFrom: 0,
+    });
+    await govern(multisig, multisigSigner, ownerA, "change_threshold", {
+      threshold: new anchor.BN(3),
+    });
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const data = program.coder.instruction.encode("set_min_slot_delay", {
+      minSlotDelay: new anchor.BN(0),
+    });
+    const expired = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      data
+    );
+    await approve(multisig, expired, ownerB);
+    await sleep(6000);
+
+    const importApprovals = (transaction, source) =>
+      program.rpc.importApprovals({
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: transaction.publicKey,
+          source: source.publicKey,
+          owner: ownerC.publicKey,
+        },
+        signers: [ownerC],
+      });
+
+    // Different content doesn't inherit anything.
+    const other = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("set_min_slot_delay", {
+        minSlotDelay: new anchor.BN(1),
+      })
+    );
+    await assertProgramError(
+      importApprovals(other, expired),
+      "The transaction's content doesn't match its content hash."
+    );
+
+    const recreated = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      data
+    );
+    // B rejected the re-created proposal, which their old approval doesn't
+    // override.
+    await program.rpc.reject({
+      accounts: {
+        multisig: multisig.publicKey,
+        transaction: recreated.publicKey,
+        owner: ownerB.publicKey,
+      },
+      signers: [ownerB],
+    });
+    await importApprovals(recreated, expired);
+    const imported = await program.account.transaction.fetch(
+      recreated.publicKey
+    );
+    assert.deepStrictEqual(signedBy(imported), [true, false, false]);
+    assert.deepStrictEqual(imported.rejecters, [false, true, false]);
+
+    // The approvals moved, the source can't hand them out again.
+    const source = await program.account.transaction.fetch(expired.publicKey);
+    assert.deepStrictEqual(source.status, { cancelled: {} });
+    assert.deepStrictEqual(signedBy(source), [false, false, false]);
+
+    await approve(multisig, recreated, ownerB);
+    await approve(multisig, recreated, ownerC);
+    await executeTransaction(multisig, multisigSigner, recreated, accounts);
+  });
+
+  it("Refuses to import the approvals of a live proposal", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      2
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const data = program.coder.instruction.encode("set_min_slot_delay", {
+      minSlotDelay: new anchor.BN(0),
+    });
+    const live = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      data
+    );
+    await approve(multisig, live, ownerB);
+    const copy = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      data
+    );
+
+    // Importing would let one set of approvals execute twice.
+    await assertProgramError(
+      program.rpc.importApprovals({
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: copy.publicKey,
+          source: live.publicKey,
+          owner: ownerA.publicKey,
+        },
+        signers: [ownerA],
+      }),
+      "The source must be another transaction of the multisig that expired or was cancelled."
+    );
+    assert.deepStrictEqual(
+      signedBy(await program.account.transaction.fetch(copy.publicKey)),
+      [true, false]
+    );
+    await executeTransaction(multisig, multisigSigner, live, accounts);
+  });
+
+  it("Weighs approvals by the owners' escrow contributions", async () => {
+    const connection = program.provider.connection;
+    const ownerA = program.provider.wallet.payer;
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    for (const owner of [ownerB, ownerC]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(
+          owner.publicKey,
+          anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      1
+    );
+    // Without contributions, no one could reach the threshold.
+    await assertProgramError(
+      govern(multisig, multisigSigner, ownerA, "set_weight_by_contribution", {
+        enabled: true,
+      }),
+      "Threshold must be less than or equal to the number of owners."
+    );
+    const deposit = async (depositor, amount) => {
+      const transaction = anchor.web3.Keypair.generate();
+      await program.rpc.depositAndProposeWithdrawal(
+        new anchor.BN(amount),
+        new anchor.BN(0),
+        depositor.publicKey,
+        noReference,
+        {
+          accounts: {
+            multisig: multisig.publicKey,
+            multisigSigner,
+            transaction: transaction.publicKey,
+            depositor: depositor.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          },
+          instructions: [
+            await program.account.transaction.createInstruction(
+              transaction,
+              1000
+            ),
+          ],
+          signers: [transaction, depositor],
+        }
+      );
+    };
+    await deposit(ownerA, 3000000);
+    await deposit(ownerB, 1000000);
+    await deposit(ownerC, 1000000);
+    assert.deepStrictEqual(
+      (await program.account.multisig.fetch(
+        multisig.publicKey
+      )).contributions.map((contribution) => contribution.toNumber()),
+      [3000000, 1000000, 1000000]
+    );
+
+    await govern(
+      multisig,
+      multisigSigner,
+      ownerA,
+      "set_weight_by_contribution",
+      { enabled: true }
+    );
+    await govern(multisig, multisigSigner, ownerA, "change_threshold", {
+      threshold: new anchor.BN(3000000),
+    });
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const data = program.coder.instruction.encode("set_min_slot_delay", {
+      minSlotDelay: new anchor.BN(0),
+    });
+    // Two smaller contributors don't add up to the threshold...
+    const fromSmall = await createTransaction(
+      multisig,
+      ownerB,
+      program.programId,
+      accounts,
+      data
+    );
+    await approve(multisig, fromSmall, ownerC);
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, fromSmall, accounts),
+      "Not enough owners signed this transaction."
+    );
+    // ...while the largest one reaches it alone.
+    const fromLarge = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      data
+    );
+    await executeTransaction(multisig, multisigSigner, fromLarge, accounts);
+
+    // New owners would start without contributions, leaving the threshold
+    // to whoever deposits first.
+    await assertProgramError(
+      govern(multisig, multisigSigner, ownerA, "set_owners", {
+        owners: [ownerA.publicKey, ownerB.publicKey],
+        weights: [],
+      }),
+      "The owners can't be replaced while they're weighed by their contributions."
+    );
+    // Nor can the others, short of the threshold, be left on their own.
+    await assertProgramError(
+      govern(multisig, multisigSigner, ownerA, "remove_owner", {
+        owner: ownerA.publicKey,
+      }),
+      "Threshold must be less than or equal to the number of owners."
+    );
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.strictEqual(multisigAccount.owners.length, 3);
+    assert.strictEqual(multisigAccount.threshold.toNumber(), 3000000);
+  });
+
+  it("Prunes executed transactions in bulk, refunding their rent", async () => {
+    const connection = program.provider.connection;
+    const owner = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    const executed = [];
+    for (let i = 0; i < 3; i++) {
+      executed.push(
+        await govern(multisig, multisigSigner, owner, "set_min_slot_delay", {
+          minSlotDelay: new anchor.BN(0),
+        })
+      );
+    }
+    const pending = await createTransaction(
+      multisig,
+      owner,
+      program.programId,
+      authAccounts(multisig, multisigSigner),
+      program.coder.instruction.encode("set_min_slot_delay", {
+        minSlotDelay: new anchor.BN(0),
+      })
+    );
+
+    const collector = anchor.web3.Keypair.generate().publicKey;
+    let rent = 0;
+    for (const transaction of executed) {
+      rent += await connection.getBalance(transaction.publicKey);
+    }
+    await program.rpc.pruneExecuted({
+      accounts: {
+        multisig: multisig.publicKey,
+        owner: owner.publicKey,
+        collector,
+      },
+      remainingAccounts: executed.concat(pending).map((transaction) => ({
+        pubkey: transaction.publicKey,
+        isWritable: true,
+        isSigner: false,
+      })),
+      signers: [owner],
+    });
+
+    assert.strictEqual(await connection.getBalance(collector), rent);
+    for (const transaction of executed) {
+      assert.strictEqual(
+        await connection.getAccountInfo(transaction.publicKey),
+        null
+      );
+    }
+    assert.ok(!(await program.account.transaction.fetch(pending.publicKey))
+      .didExecute);
+  });
+
+  it("Rejects trivial instruction data once enabled", async () => {
+    const owner = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    // Accepted while off.
+    await createTransaction(
+      multisig,
+      owner,
+      program.programId,
+      accounts,
+      Buffer.alloc(16)
+    );
+    await govern(multisig, multisigSigner, owner, "set_reject_trivial_data", {
+      enabled: true,
+    });
+
+    for (const data of [Buffer.alloc(16), Buffer.alloc(8, 7), Buffer.alloc(0)]) {
+      await assertProgramError(
+        createTransaction(multisig, owner, program.programId, accounts, data),
+        "The instruction data is empty or a single repeated byte."
+      );
+    }
+    await createTransaction(
+      multisig,
+      owner,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("set_min_slot_delay", {
+        minSlotDelay: new anchor.BN(0),
+      })
+    );
+  });
+
+  it("Labels transaction category tags", async () => {
+    const owner = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, owner, "set_tag_label", {
+      tag: 2,
+      label: "Program Upgrade",
+    });
+    assert.deepStrictEqual(
+      (await program.account.multisig.fetch(multisig.publicKey)).tagLabels,
+      ["", "", "Program Upgrade"]
+    );
+
+    await assertProgramError(
+      govern(multisig, multisigSigner, owner, "set_tag_label", {
+        tag: 0,
+        label: "x".repeat(33),
+      }),
+      "The tag label is too long."
+    );
+  });
+
+  it("Requires unanimous approval to add an owner once enabled", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const newcomer = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      1
+    );
+    await govern(
+      multisig,
+      multisigSigner,
+      ownerA,
+      "set_add_owner_requires_unanimous",
+      { enabled: true }
+    );
+    await govern(multisig, multisigSigner, ownerA, "change_threshold", {
+      threshold: new anchor.BN(2),
+    });
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    // Anything else only needs the threshold.
+    const other = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("set_min_slot_delay", {
+        minSlotDelay: new anchor.BN(0),
+      })
+    );
+    await approve(multisig, other, ownerB);
+    await executeTransaction(multisig, multisigSigner, other, accounts);
+
+    const addOwner = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("add_owner", {
+        owner: newcomer.publicKey,
+      })
+    );
+    await approve(multisig, addOwner, ownerB);
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, addOwner, accounts),
+      "Adding an owner requires the approval of every current owner."
+    );
+    await approve(multisig, addOwner, ownerC);
+    await executeTransaction(multisig, multisigSigner, addOwner, accounts);
+
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.deepStrictEqual(
+      multisigAccount.owners.map((owner) => owner.toString()),
+      [ownerA, ownerB, ownerC, newcomer].map((owner) =>
+        owner.publicKey.toString()
+      )
+    );
+    assert.strictEqual(multisigAccount.ownerSetSeqno, 1);
+  });
+
+  it("Logs the signers as a compact bitmap", async () => {
+    const owners = [...Array(10)].map(() => anchor.web3.Keypair.generate());
+    const { multisig, multisigSigner } = await createMultisig(
+      owners.map((owner) => owner.publicKey),
+      10
+    );
+    const transaction = await createTransaction(
+      multisig,
+      owners[0],
+      program.programId,
+      authAccounts(multisig, multisigSigner),
+      program.coder.instruction.encode("set_min_slot_delay", {
+        minSlotDelay: new anchor.BN(0),
+      })
+    );
+    for (const index of [3, 8, 9]) {
+      await approve(multisig, transaction, owners[index]);
+    }
+
+    const simulation = await program.simulate.getSignerBitmap({
+      accounts: {
+        multisig: multisig.publicKey,
+        transaction: transaction.publicKey,
+      },
+    });
+    const line = simulation.raw.find((log) =>
+      log.startsWith("Program log: bitmap: ")
+    );
+    const [, encoded, count] = line.match(
+      /^Program log: bitmap: (\S*) owners: (\d+)$/
+    );
+    const bitmap = Buffer.from(encoded, "base64");
+    assert.strictEqual(bitmap.length, 8);
+    const signed = [...Array(Number(count)).keys()].filter(
+      (index) => bitmap[index >> 3] & (1 << (index & 7))
+    );
+    assert.deepStrictEqual(signed, [0, 3, 8, 9]);
+  });
+
+  it("Replaces an owner, handing their approvals to the new key", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const rotated = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      2
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const pending = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("set_min_slot_delay", {
+        minSlotDelay: new anchor.BN(0),
+      })
+    );
+    await approve(multisig, pending, ownerB);
+
+    const replace = async (acknowledgeRisk) => {
+      const transaction = await createTransaction(
+        multisig,
+        ownerA,
+        program.programId,
+        accounts,
+        program.coder.instruction.encode("replace_owner_keep_approvals", {
+          old: ownerB.publicKey,
+          new: rotated.publicKey,
+          acknowledgeRisk,
+        })
+      );
+      await approve(multisig, transaction, ownerC);
+      await executeTransaction(multisig, multisigSigner, transaction, accounts);
+    };
+    await assertProgramError(
+      replace(false),
+      "Keeping approvals across an owner replacement must be acknowledged."
+    );
+    await replace(true);
+
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.ok(multisigAccount.owners[1].equals(rotated.publicKey));
+    assert.strictEqual(multisigAccount.ownerSetSeqno, 0);
+    // Owner B's approval now counts as the rotated key's.
+    assert.deepStrictEqual(
+      signedBy(await program.account.transaction.fetch(pending.publicKey)),
+      [true, true, false]
+    );
+    await executeTransaction(multisig, multisigSigner, pending, accounts);
+  });
+
+  it("Rejects nonces that don't derive an off-curve signer", async () => {
+    const multisig = anchor.web3.Keypair.generate();
+    // Find a nonce whose derived address lies on the curve.
+    let nonce = 255;
+    for (; nonce >= 0; nonce--) {
+      try {
+        await anchor.web3.PublicKey.createProgramAddress(
+          [multisig.publicKey.toBuffer(), Buffer.from([nonce])],
+          program.programId
+        );
+      } catch (err) {
+        break;
+      }
+    }
+    assert.ok(nonce >= 0, "no on-curve nonce found");
+
+    await assertProgramError(
+      program.rpc.createMultisig(
+        "test multisig",
+        [program.provider.wallet.publicKey],
+        new anchor.BN(1),
+        nonce,
+        {
+        strict: false,
+        emergencyRecipient: anchor.web3.PublicKey.default,
+        weights: [],
+      },
+        {
+          accounts: {
+            multisig: multisig.publicKey,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          },
+          instructions: [
+            await program.account.multisig.createInstruction(multisig, 1000),
+          ],
+          signers: [multisig],
+        }
+      ),
+      "The nonce doesn't derive a valid multisig signer address."
+    );
+    // The canonical nonce is fine, as every other test shows.
+    await createMultisig([program.provider.wallet.publicKey], 1);
+  });
+
+  it("Records a threshold-approved heartbeat", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      2
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("heartbeat", {})
+    );
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts),
+      "Not enough owners signed this transaction."
+    );
+    assert.ok(
+      (await program.account.multisig.fetch(
+        multisig.publicKey
+      )).lastHeartbeatAt.isZero()
+    );
+
+    await approve(multisig, transaction, ownerB);
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+    const txAccount = await program.account.transaction.fetch(
+      transaction.publicKey
+    );
+    const { lastHeartbeatAt } = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.ok(lastHeartbeatAt.gte(txAccount.createdAt));
+  });
+
+  it("Refuses to write to the executor's account once protected", async () => {
+    const owner = anchor.web3.Keypair.generate();
+    const keeper = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, owner, "set_protect_executor", {
+      enabled: true,
+    });
+
+    const data = program.coder.instruction.encode("set_min_slot_delay", {
+      minSlotDelay: new anchor.BN(0),
+    });
+    const targeting = authAccounts(multisig, multisigSigner, [
+      { pubkey: keeper.publicKey, isWritable: true, isSigner: false },
+    ]);
+    const malicious = await createTransaction(
+      multisig,
+      owner,
+      program.programId,
+      targeting,
+      data
+    );
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, malicious, targeting, {
+        executor: keeper,
+      }),
+      "The transaction would write to the executor's account."
+    );
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const benign = await createTransaction(
+      multisig,
+      owner,
+      program.programId,
+      accounts,
+      data
+    );
+    await executeTransaction(multisig, multisigSigner, benign, accounts, {
+      executor: keeper,
+    });
+  });
+
+  it("Renames the whole board at once", async () => {
+    const owners = [...Array(4)].map(() => anchor.web3.Keypair.generate());
+    const { multisig, multisigSigner } = await createMultisig(
+      owners.map((owner) => owner.publicKey),
+      1
+    );
+    const setNames = (names) =>
+      govern(multisig, multisigSigner, owners[0], "set_owner_names", {
+        names,
+      });
+
+    await setNames(["alice", "bob", "carol", "dave"]);
+    await setNames(["erin", "frank", "grace", "heidi"]);
+    assert.deepStrictEqual(
+      (await program.account.multisig.fetch(multisig.publicKey)).names,
+      ["erin", "frank", "grace", "heidi"]
+    );
+
+    await assertProgramError(
+      setNames(["ivan", "judy", "mallory"]),
+      "There must be one name per owner, each within the length limit."
+    );
+    await assertProgramError(
+      setNames(["ivan", "judy", "mallory", "n".repeat(33)]),
+      "There must be one name per owner, each within the length limit."
+    );
+  });
+
+  it("Deposits lamports into the escrow", async () => {
+    const provider = program.provider;
+    const owner = provider.wallet.payer;
+    const outsider = anchor.web3.Keypair.generate();
+    const { multisig } = await createMultisig([owner.publicKey], 1);
+    const deposit = (depositor, lamports) =>
+      program.rpc.depositLamports(new anchor.BN(lamports), {
+        accounts: {
+          multisig: multisig.publicKey,
+          owner: depositor.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+        signers: depositor === owner ? [] : [depositor],
+      });
+
+    const balance = await provider.connection.getBalance(multisig.publicKey);
+    await deposit(owner, 1000000);
+    assert.strictEqual(
+      await provider.connection.getBalance(multisig.publicKey),
+      balance + 1000000
+    );
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.ok(multisigAccount.lamports.eq(new anchor.BN(1000000)));
+    assert.ok(multisigAccount.contributions[0].eq(new anchor.BN(1000000)));
+
+    await assertProgramError(
+      deposit(owner, 0),
+      "Deposits must be of at least one lamport."
+    );
+    await assertProgramError(
+      deposit(outsider, 1),
+      "The given owner is not part of this multisig."
+    );
+  });
+
+  it("Ties withdrawals to pre-authorized references", async () => {
+    const provider = program.provider;
+    const depositor = provider.wallet.payer;
+    const { multisig, multisigSigner } = await createMultisig(
+      [depositor.publicKey],
+      1
+    );
+    const invoice = [...crypto.randomBytes(32)];
+    await govern(
+      multisig,
+      multisigSigner,
+      depositor,
+      "set_withdraw_reference_policy",
+      { required: true, references: [invoice] }
+    );
+
+    const destination = anchor.web3.Keypair.generate().publicKey;
+    const withdraw = async (reference) => {
+      const transaction = anchor.web3.Keypair.generate();
+      await program.rpc.depositAndProposeWithdrawal(
+        new anchor.BN(1000000),
+        new anchor.BN(1000000),
+        destination,
+        reference,
+        {
+          accounts: {
+            multisig: multisig.publicKey,
+            multisigSigner,
+            transaction: transaction.publicKey,
+            depositor: depositor.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          },
+          instructions: [
+            await program.account.transaction.createInstruction(
+              transaction,
+              1000
+            ),
+          ],
+          signers: [transaction],
+        }
+      );
+      const txAccount = await program.account.transaction.fetch(
+        transaction.publicKey
+      );
+      await executeTransaction(
+        multisig,
+        multisigSigner,
+        transaction,
+        txAccount.accounts
+      );
+    };
+
+    await assertProgramError(
+      withdraw(noReference),
+      "Withdrawals must be made under a non-zero reference."
+    );
+    await assertProgramError(
+      withdraw([...crypto.randomBytes(32)]),
+      "The withdrawal reference isn't one of the pre-authorized ones."
+    );
+    await withdraw(invoice);
+    assert.strictEqual(
+      await provider.connection.getBalance(destination),
+      1000000
+    );
+  });
+
+  it("Splits the escrow equally among the owners", async () => {
+    const connection = program.provider.connection;
+    const owners = [...Array(3)].map(() => anchor.web3.Keypair.generate());
+    const [ownerA, ownerB, ownerC] = owners;
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(
+        ownerA.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+    const { multisig, multisigSigner } = await createMultisig(
+      owners.map((owner) => owner.publicKey),
+      1
+    );
+    await program.rpc.depositLamports(new anchor.BN(3000002), {
+      accounts: {
+        multisig: multisig.publicKey,
+        owner: ownerA.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      },
+      signers: [ownerA],
+    });
+
+    const ownerAccounts = (keys) =>
+      keys.map((owner) => ({
+        pubkey: owner.publicKey,
+        isWritable: true,
+        isSigner: false,
+      }));
+    const withdraw = (keys) =>
+      govern(
+        multisig,
+        multisigSigner,
+        ownerA,
+        "withdraw_lamports",
+        { reference: noReference },
+        ownerAccounts(keys)
+      );
+
+    // Every owner, in order, has to be paid.
+    await assertProgramError(
+      withdraw([ownerA, ownerB]),
+      "The accounts given don't match the current owners, in order."
+    );
+    await assertProgramError(
+      withdraw([ownerA, ownerC, ownerB]),
+      "The accounts given don't match the current owners, in order."
+    );
+
+    const balances = async () => {
+      const result = [];
+      for (const owner of owners) {
+        result.push(await connection.getBalance(owner.publicKey));
+      }
+      return result;
+    };
+    const before = await balances();
+    await withdraw(owners);
+    const after = await balances();
+    assert.deepStrictEqual(
+      after.map((balance, index) => balance - before[index]),
+      [1000002, 1000000, 1000000]
+    );
+    assert.ok(
+      (await program.account.multisig.fetch(multisig.publicKey)).lamports.isZero()
+    );
+  });
+
+  it("Lets owners revoke their approval before execution", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const outsider = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      2
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("set_min_slot_delay", {
+        minSlotDelay: new anchor.BN(0),
+      })
+    );
+    const revoke = (owner) =>
+      program.rpc.revoke({
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: transaction.publicKey,
+          owner: owner.publicKey,
+        },
+        signers: [owner],
+      });
+
+    await approve(multisig, transaction, ownerB);
+    await revoke(ownerB);
+    assert.deepStrictEqual(
+      signedBy(await program.account.transaction.fetch(transaction.publicKey)),
+      [true, false]
+    );
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts),
+      "Not enough owners signed this transaction."
+    );
+    // There's nothing left for B to revoke.
+    await assertProgramError(
+      revoke(ownerB),
+      "The owner hasn't approved this transaction."
+    );
+    await assertProgramError(
+      revoke(outsider),
+      "The given owner is not part of this multisig."
+    );
+
+    await approve(multisig, transaction, ownerB);
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+    await assertProgramError(
+      revoke(ownerB),
+      "The given transaction has already been executed."
+    );
+  });
+
+  it("Numbers proposals sequentially with a short content hash", async () => {
+    const owner = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const proposals = [];
+    for (let delay = 0; delay < 3; delay++) {
+      const data = program.coder.instruction.encode("set_min_slot_delay", {
+        minSlotDelay: new anchor.BN(delay),
+      });
+      const transaction = await createTransaction(
+        multisig,
+        owner,
+        program.programId,
+        accounts,
+        data
+      );
+      proposals.push({
+        account: await program.account.transaction.fetch(
+          transaction.publicKey
+        ),
+        hash: contentHash(program.programId, accounts, data),
+      });
+    }
+
+    assert.deepStrictEqual(
+      proposals.map(({ account }) => account.id.toNumber()),
+      [0, 1, 2]
+    );
+    for (const { account, hash } of proposals) {
+      assert.deepStrictEqual(Buffer.from(account.shortHash), hash.slice(0, 8));
+    }
+    const shortHashes = proposals.map(({ account }) =>
+      Buffer.from(account.shortHash).toString("hex")
+    );
+    assert.strictEqual(new Set(shortHashes).size, 3);
+  });
+
+  it("Deletes unapproved proposals, refunding rent to the proposer", async () => {
+    const provider = program.provider;
+    const proposer = anchor.web3.Keypair.generate();
+    const other = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [proposer.publicKey, other.publicKey],
+      2
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const data = program.coder.instruction.encode("set_min_slot_delay", {
+      minSlotDelay: new anchor.BN(0),
+    });
+    const deleteTransaction = (transaction, caller) =>
+      program.rpc.deleteTransaction({
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: transaction.publicKey,
+          proposer: caller.publicKey,
+        },
+        signers: [caller],
+      });
+
+    const approved = await createTransaction(
+      multisig,
+      proposer,
+      program.programId,
+      accounts,
+      data
+    );
+    await approve(multisig, approved, other);
+    await assertProgramError(
+      deleteTransaction(approved, proposer),
+      "Cannot delete a transaction that has been signed by an owner."
+    );
+
+    // Nor once another owner approved after the proposer revoked.
+    const endorsed = await createTransaction(
+      multisig,
+      proposer,
+      program.programId,
+      accounts,
+      data
+    );
+    await program.rpc.revoke({
+      accounts: {
+        multisig: multisig.publicKey,
+        transaction: endorsed.publicKey,
+        owner: proposer.publicKey,
+      },
+      signers: [proposer],
+    });
+    await approve(multisig, endorsed, other);
+    await assertProgramError(
+      deleteTransaction(endorsed, proposer),
+      "Cannot delete a transaction that has been signed by an owner."
+    );
+
+    const transaction = await createTransaction(
+      multisig,
+      proposer,
+      program.programId,
+      accounts,
+      data
+    );
+    await assertProgramError(
+      deleteTransaction(transaction, other),
+      "Cannot delete a transaction the owner did not create."
+    );
+
+    const rent = await provider.connection.getBalance(transaction.publicKey);
+    const before = await provider.connection.getBalance(proposer.publicKey);
+    await deleteTransaction(transaction, proposer);
+    assert.strictEqual(
+      await provider.connection.getAccountInfo(transaction.publicKey),
+      null
+    );
+    assert.strictEqual(
+      await provider.connection.getBalance(proposer.publicKey),
+      before + rent
+    );
+  });
+
+  it("Withdraws the escrow to the emergency recipient after a delay", async () => {
+    const provider = program.provider;
+    const ownerA = provider.wallet.payer;
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const recipient = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      2,
+      1000,
+      false,
+      recipient.publicKey
+    );
+    // Emergency withdrawals take the regular threshold until lowered.
+    assert.strictEqual(
+      (
+        await program.account.multisig.fetch(multisig.publicKey)
+      ).emergencyThreshold.toNumber(),
+      2
+    );
+
+    // A single owner may initiate, completing two seconds later.
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("set_emergency_policy", {
+        threshold: new anchor.BN(1),
+        delay: new anchor.BN(2),
+      })
+    );
+    await approve(multisig, transaction, ownerB);
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+
+    await program.rpc.depositLamports(new anchor.BN(1000000), {
+      accounts: {
+        multisig: multisig.publicKey,
+        owner: ownerA.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      },
+    });
+
+    const emergency = (name, owner) =>
+      program.rpc[name]({
+        accounts: { multisig: multisig.publicKey, owner: owner.publicKey },
+        signers: [owner],
+      });
+    const withdraw = () =>
+      program.rpc.emergencyWithdraw({
+        accounts: {
+          multisig: multisig.publicKey,
+          emergencyRecipient: recipient.publicKey,
+        },
+      });
+    const initiatedAt = async () =>
+      (
+        await program.account.multisig.fetch(multisig.publicKey)
+      ).emergencyInitiatedAt.toNumber();
+
+    await assertProgramError(
+      withdraw(),
+      "No emergency withdrawal has been initiated."
+    );
+
+    await emergency("initiateEmergency", ownerB);
+    assert.ok((await initiatedAt()) > 0);
+    await emergency("cancelEmergency", ownerC);
+    assert.strictEqual(await initiatedAt(), 0);
+
+    await emergency("initiateEmergency", ownerB);
+    await assertProgramError(
+      emergency("initiateEmergency", ownerC),
+      "An emergency withdrawal is already in progress."
+    );
+    await assertProgramError(
+      withdraw(),
+      "The emergency withdrawal delay hasn't elapsed yet."
+    );
+
+    await sleep(3000);
+    await withdraw();
+    assert.strictEqual(
+      await provider.connection.getBalance(recipient.publicKey),
+      1000000
+    );
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.strictEqual(multisigAccount.lamports.toNumber(), 0);
+    assert.strictEqual(multisigAccount.emergencyInitiatedAt.toNumber(), 0);
+  });
+
+  it("Warns about proposals too large to execute", async () => {
+    const owner = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    const checkExecutionSize = async (accounts) => {
+      const transaction = await createTransaction(
+        multisig,
+        owner,
+        anchor.web3.SystemProgram.programId,
+        accounts,
+        Buffer.alloc(8)
+      );
+      const simulation = await program.simulate.checkExecutionSize({
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: transaction.publicKey,
+        },
+      });
+      return simulation.raw.some((log) =>
+        log.startsWith("Program log: Warning: execution likely exceeds")
+      );
+    };
+
+    assert.ok(
+      !(await checkExecutionSize(authAccounts(multisig, multisigSigner)))
+    );
+    // Every extra signer has to sign the executing transaction.
+    const signers = [...Array(12)].map(() => ({
+      pubkey: anchor.web3.Keypair.generate().publicKey,
+      isWritable: true,
+      isSigner: true,
+    }));
+    assert.ok(await checkExecutionSize(signers));
+  });
+
+  it("Rejects empty and duplicate owner lists", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    await assertProgramError(
+      createMultisig([], 1),
+      "The given owner is not part of this multisig."
+    );
+    await assertProgramError(
+      createMultisig([ownerA.publicKey, ownerB.publicKey, ownerA.publicKey], 2),
+      "The owner is already part of this multisig."
+    );
+
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      1
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    for (const owners of [[], [ownerB.publicKey, ownerB.publicKey]]) {
+      const transaction = await createTransaction(
+        multisig,
+        ownerA,
+        program.programId,
+        accounts,
+        program.coder.instruction.encode("set_owners", { owners, weights: [] })
+      );
+      await assertProgramError(
+        executeTransaction(multisig, multisigSigner, transaction, accounts),
+        owners.length === 0
+          ? "The given owner is not part of this multisig."
+          : "The owner is already part of this multisig."
+      );
+    }
+    const { owners } = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.deepStrictEqual(
+      owners.map((owner) => owner.toBase58()),
+      [ownerA.publicKey.toBase58(), ownerB.publicKey.toBase58()]
+    );
+  });
+
+  it("Lets a high-weight owner veto an approved transaction", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_weight", {
+      owner: ownerA.publicKey,
+      weight: new anchor.BN(3),
+    });
+    await govern(multisig, multisigSigner, ownerA, "set_reject_threshold", {
+      rejectThreshold: new anchor.BN(3),
+    });
+    await govern(multisig, multisigSigner, ownerA, "change_threshold", {
+      threshold: new anchor.BN(2),
+    });
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerB,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("set_min_slot_delay", {
+        minSlotDelay: new anchor.BN(0),
+      })
+    );
+    await approve(multisig, transaction, ownerC);
+    await program.rpc.reject({
+      accounts: {
+        multisig: multisig.publicKey,
+        transaction: transaction.publicKey,
+        owner: ownerA.publicKey,
+      },
+      signers: [ownerA],
+    });
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts),
+      "The transaction was rejected by enough owners."
+    );
+  });
+
+  it("Rejects thresholds out of bounds at creation", async () => {
+    const owners = [...Array(3)].map(
+      () => anchor.web3.Keypair.generate().publicKey
+    );
+    for (const threshold of [0, 4]) {
+      await assertProgramError(
+        createMultisig(owners, threshold),
+        "Threshold must be less than or equal to the number of owners."
+      );
+    }
+    await createMultisig(owners, 3);
+  });
+
+  it("Rotates a mint authority to a newly derived signer", async () => {
+    const provider = program.provider;
+    const owner = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner, nonce } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    const mint = await Token.createMint(
+      provider.connection,
+      provider.wallet.payer,
+      multisigSigner,
+      null,
+      0,
+      TOKEN_PROGRAM_ID
+    );
+
+    // Any other nonce deriving an off-curve address will do.
+    let newNonce = nonce - 1;
+    let newSigner;
+    for (; newNonce >= 0; newNonce--) {
+      try {
+        newSigner = await anchor.web3.PublicKey.createProgramAddress(
+          [multisig.publicKey.toBuffer(), Buffer.from([newNonce])],
+          program.programId
+        );
+        break;
+      } catch (err) {
+        continue;
+      }
+    }
+    assert.ok(newSigner, "no other off-curve nonce found");
+
+    await govern(
+      multisig,
+      multisigSigner,
+      owner,
+      "rotate_signer",
+      { nonce: newNonce },
+      [
+        { pubkey: newSigner, isWritable: false, isSigner: false },
+        { pubkey: TOKEN_PROGRAM_ID, isWritable: false, isSigner: false },
+        { pubkey: mint.publicKey, isWritable: true, isSigner: false },
+      ]
+    );
+
+    const mintInfo = await mint.getMintInfo();
+    assert.ok(mintInfo.mintAuthority.equals(newSigner));
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.strictEqual(multisigAccount.nonce, newNonce);
+
+    // The multisig is governed through the new signer from now on.
+    await govern(multisig, newSigner, owner, "set_min_slot_delay", {
+      minSlotDelay: new anchor.BN(0),
+    });
+  });
+
+  it("Holds executions back until the timelock elapses", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_timelock", {
+      timelock: new anchor.BN(2),
+    });
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("change_threshold", {
+        threshold: new anchor.BN(2),
+      })
+    );
+    const { readyAt } = await program.account.transaction.fetch(
+      transaction.publicKey
+    );
+    assert.ok(readyAt.toNumber() > 0);
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts),
+      "The transaction's timelock hasn't elapsed yet."
+    );
+
+    await sleep(3000);
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.strictEqual(multisigAccount.threshold.toNumber(), 2);
+  });
+
+  it("Refuses to approve or execute proposals past the maximum age", async () => {
+    const owner = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, owner, "set_max_proposal_age", {
+      maxProposalAge: new anchor.BN(2),
+    });
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const propose = () =>
+      createTransaction(
+        multisig,
+        owner,
+        program.programId,
+        accounts,
+        program.coder.instruction.encode("rename", { description: "aged" })
+      );
+    const aged = await propose();
+    await sleep(4000);
+    await assertProgramError(
+      approve(multisig, aged, owner),
+      "The transaction is older than the multisig's maximum proposal age."
+    );
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, aged, accounts),
+      "The transaction is older than the multisig's maximum proposal age."
+    );
+
+    const fresh = await propose();
+    await executeTransaction(multisig, multisigSigner, fresh, accounts);
+  });
+
+  it("Counts proposals by state", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_reject_threshold", {
+      rejectThreshold: new anchor.BN(2),
+    });
+    await govern(multisig, multisigSigner, ownerA, "set_max_proposal_age", {
+      maxProposalAge: new anchor.BN(3),
+    });
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const propose = () =>
+      createTransaction(
+        multisig,
+        ownerA,
+        program.programId,
+        accounts,
+        program.coder.instruction.encode("rename", { description: "stats" })
+      );
+    const expired = await propose();
+    const executed = await propose();
+    await executeTransaction(multisig, multisigSigner, executed, accounts);
+    await sleep(4500);
+    const active = await propose();
+    const rejected = await propose();
+    for (const owner of [ownerB, ownerC]) {
+      await program.rpc.reject({
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: rejected.publicKey,
+          owner: owner.publicKey,
+        },
+        signers: [owner],
+      });
+    }
+
+    const simulation = await program.simulate.getProposalStats({
+      accounts: { multisig: multisig.publicKey },
+      remainingAccounts: [expired, executed, active, rejected].map((tx) => ({
+        pubkey: tx.publicKey,
+        isWritable: false,
+        isSigner: false,
+      })),
+    });
+    assert.ok(
+      simulation.raw.includes(
+        "Program log: active: 1 executed: 1 expired: 1 rejected: 1"
+      )
+    );
+  });
+
+  it("Never removes the permanent owner", async () => {
+    const founder = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [founder.publicKey, ownerB.publicKey, ownerC.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, founder, "set_permanent_owner", {
+      owner: founder.publicKey,
+    });
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const propose = async (name, args) => {
+      const transaction = await createTransaction(
+        multisig,
+        ownerB,
+        program.programId,
+        accounts,
+        program.coder.instruction.encode(name, args)
+      );
+      return executeTransaction(multisig, multisigSigner, transaction, accounts);
+    };
+    await assertProgramError(
+      propose("set_owners", {
+        owners: [ownerB.publicKey, ownerC.publicKey],
+        weights: [],
+      }),
+      "The permanent owner can't be removed or changed."
+    );
+    await assertProgramError(
+      propose("replace_owner_keep_approvals", {
+        old: founder.publicKey,
+        new: anchor.web3.Keypair.generate().publicKey,
+        acknowledgeRisk: true,
+      }),
+      "The permanent owner can't be removed or changed."
+    );
+    await assertProgramError(
+      propose("set_permanent_owner", { owner: ownerB.publicKey }),
+      "The permanent owner can't be removed or changed."
+    );
+
+    await propose("set_owners", {
+      owners: [founder.publicKey, ownerB.publicKey],
+      weights: [],
+    });
+    const { owners } = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.deepStrictEqual(
+      owners.map((owner) => owner.toBase58()),
+      [founder.publicKey.toBase58(), ownerB.publicKey.toBase58()]
+    );
+  });
+
+  it("Caps the number of owners", async () => {
+    const owners = [...Array(20)].map(() => anchor.web3.Keypair.generate());
+    const { multisig, multisigSigner } = await createMultisig(
+      owners.map((owner) => owner.publicKey),
+      1,
+      3000
+    );
+
+    // Larger owner sets don't fit into a single transaction, grow it instead.
+    const accounts = authAccounts(multisig, multisigSigner);
+    const addOwner = async () => {
+      const transaction = await createTransaction(
+        multisig,
+        owners[0],
+        program.programId,
+        accounts,
+        program.coder.instruction.encode("add_owner", {
+          owner: anchor.web3.Keypair.generate().publicKey,
+        }),
+        4000
+      );
+      await executeTransaction(multisig, multisigSigner, transaction, accounts);
+    };
+    for (let count = owners.length; count < 64; count++) {
+      await addOwner();
+    }
+    await assertProgramError(addOwner(), "The multisig can't have more owners.");
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.strictEqual(multisigAccount.owners.length, 64);
+  });
+
+  it("Proposes, approves and executes a 3-of-3 action in one instruction", async () => {
+    const owners = [...Array(3)].map(() => anchor.web3.Keypair.generate());
+    const [ownerA, ownerB, ownerC] = owners;
+    const { multisig, multisigSigner } = await createMultisig(
+      owners.map((owner) => owner.publicKey),
+      3
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const data = program.coder.instruction.encode("rename", {
+      description: "one shot",
+    });
+    const proposeApproveExecute = async (cosigners) => {
+      const transaction = anchor.web3.Keypair.generate();
+      await program.rpc.proposeApproveExecute(
+        program.programId,
+        accounts,
+        data,
+        {
+          accounts: {
+            multisig: multisig.publicKey,
+            multisigSigner,
+            transaction: transaction.publicKey,
+            proposer: ownerA.publicKey,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          },
+          remainingAccounts: cosigners
+            .map((owner) => ({
+              pubkey: owner.publicKey,
+              isWritable: false,
+              isSigner: true,
+            }))
+            .concat(
+              accounts.map((meta) =>
+                meta.pubkey.equals(multisigSigner)
+                  ? { ...meta, isSigner: false }
+                  : meta
+              )
+            )
+            .concat({
+              pubkey: program.programId,
+              isWritable: false,
+              isSigner: false,
+            }),
+          instructions: [
+            await program.account.transaction.createInstruction(
+              transaction,
+              1000
+            ),
+          ],
+          signers: [transaction, ownerA, ...cosigners],
+        }
+      );
+      return transaction;
+    };
+
+    await assertProgramError(
+      proposeApproveExecute([ownerB]),
+      "Not enough owners signed this transaction."
+    );
+
+    const transaction = await proposeApproveExecute([ownerB, ownerC]);
+    const txAccount = await program.account.transaction.fetch(
+      transaction.publicKey
+    );
+    assert.ok(txAccount.didExecute);
+    assert.deepStrictEqual(signedBy(txAccount), [true, true, true]);
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.strictEqual(multisigAccount.description, "one shot");
+    assert.strictEqual(multisigAccount.transactionCount.toNumber(), 1);
+  });
+
+  it("Throttles owners approving too many transactions at once", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_approval_throttle", {
+      maxApprovalsPerWindow: new anchor.BN(2),
+      approvalWindow: new anchor.BN(3600),
+    });
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transactions = [];
+    for (let i = 0; i < 3; i++) {
+      transactions.push(
+        await createTransaction(
+          multisig,
+          ownerA,
+          program.programId,
+          accounts,
+          program.coder.instruction.encode("rename", { description: `${i}` })
+        )
+      );
+    }
+    await approve(multisig, transactions[0], ownerB);
+    await approve(multisig, transactions[1], ownerB);
+    await assertProgramError(
+      approve(multisig, transactions[2], ownerB),
+      "The owner approved too many transactions in the current window."
+    );
+  });
+
+  it("Executes the instructions of a batch in order, all or nothing", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      1
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const instruction = (name, args) => ({
+      programId: program.programId,
+      accounts,
+      data: program.coder.instruction.encode(name, args),
+    });
+    const createBatch = async (instructions) => {
+      const transaction = anchor.web3.Keypair.generate();
+      await program.rpc.createTransactionBatch(instructions, {
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: transaction.publicKey,
+          proposer: ownerA.publicKey,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        },
+        instructions: [
+          await program.account.transaction.createInstruction(
+            transaction,
+            1000
+          ),
+        ],
+        signers: [transaction, ownerA],
+      });
+      return transaction;
+    };
+
+    await assertProgramError(
+      createBatch([]),
+      "A batch must have at least one instruction."
+    );
+
+    // The second instruction fails, so the rename is rolled back.
+    const failing = await createBatch([
+      instruction("rename", { description: "partial" }),
+      instruction("change_threshold", { threshold: new anchor.BN(3) }),
+    ]);
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, failing, accounts),
+      "Threshold must be less than or equal to the number of owners."
+    );
+    let multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.strictEqual(multisigAccount.description, "test multisig");
+    assert.strictEqual(multisigAccount.threshold.toNumber(), 1);
+
+    const batch = await createBatch([
+      instruction("rename", { description: "batched" }),
+      instruction("change_threshold", { threshold: new anchor.BN(2) }),
+    ]);
+    await executeTransaction(multisig, multisigSigner, batch, accounts);
+    multisigAccount = await program.account.multisig.fetch(multisig.publicKey);
+    assert.strictEqual(multisigAccount.description, "batched");
+    assert.strictEqual(multisigAccount.threshold.toNumber(), 2);
+    const txAccount = await program.account.transaction.fetch(
+      batch.publicKey
+    );
+    assert.ok(txAccount.didExecute);
+    assert.strictEqual(txAccount.instructions.length, 2);
+  });
+
+  it("Winds down the multisig, sweeping its escrow and closing it", async () => {
+    const provider = program.provider;
+    const owner = provider.wallet.payer;
+    const destination = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    await program.rpc.depositLamports(new anchor.BN(1000000), {
+      accounts: {
+        multisig: multisig.publicKey,
+        owner: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      },
+    });
+    const mint = await Token.createMint(
+      provider.connection,
+      owner,
+      owner.publicKey,
+      null,
+      0,
+      TOKEN_PROGRAM_ID
+    );
+    const funded = await mint.createAccount(multisigSigner);
+    await mint.mintTo(funded, owner, [], 5);
+    const empty = await mint.createAccount(multisigSigner);
+    const tokenAccount = (pubkey) => ({
+      pubkey,
+      isWritable: false,
+      isSigner: false,
+    });
+    const windDown = (tokenAccounts) =>
+      govern(
+        multisig,
+        multisigSigner,
+        owner,
+        "wind_down",
+        {},
+        [
+          {
+            pubkey: destination.publicKey,
+            isWritable: true,
+            isSigner: false,
+          },
+        ].concat(tokenAccounts.map(tokenAccount))
+      );
+
+    await assertProgramError(
+      windDown([funded]),
+      "A token account owned by the multisig still holds tokens."
+    );
+
+    // The escrow and the rent both go to the destination.
+    const balance = await provider.connection.getBalance(multisig.publicKey);
+    await windDown([empty]);
+    assert.strictEqual(
+      await provider.connection.getAccountInfo(multisig.publicKey),
+      null
+    );
+    assert.strictEqual(
+      await provider.connection.getBalance(destination.publicKey),
+      balance
+    );
+  });
+
+  it("Fails fast once rejections put the threshold out of reach", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      2
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("rename", { description: "vetoed" })
+    );
+    const reject = (owner) =>
+      program.rpc.reject({
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: transaction.publicKey,
+          owner: owner.publicKey,
+        },
+        signers: [owner],
+      });
+    const votes = async () => {
+      const txAccount = await program.account.transaction.fetch(
+        transaction.publicKey
+      );
+      return { signers: signedBy(txAccount), rejecters: txAccount.rejecters };
+    };
+
+    // Approving and rejecting replace each other.
+    await reject(ownerB);
+    assert.deepStrictEqual(await votes(), {
+      signers: [true, false, false],
+      rejecters: [false, true, false],
+    });
+    await approve(multisig, transaction, ownerB);
+    assert.deepStrictEqual(await votes(), {
+      signers: [true, true, false],
+      rejecters: [false, false, false],
+    });
+
+    // With two of three owners rejecting, two approvals can't be reached.
+    await reject(ownerB);
+    await reject(ownerC);
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts),
+      "The transaction was rejected by enough owners."
+    );
+  });
+
+  it("Leaves suspended owners out of the weight rejections leave", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_owner_suspended", {
+      owner: ownerC.publicKey,
+      suspended: true,
+    });
+    await govern(multisig, multisigSigner, ownerA, "change_threshold", {
+      threshold: new anchor.BN(2),
+    });
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("rename", { description: "vetoed" })
+    );
+    // With C suspended, B's rejection leaves only A's weight, short of two.
+    await program.rpc.reject({
+      accounts: {
+        multisig: multisig.publicKey,
+        transaction: transaction.publicKey,
+        owner: ownerB.publicKey,
+      },
+      signers: [ownerB],
+    });
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts),
+      "The transaction was rejected by enough owners."
+    );
+  });
+
+  it("Signs with a vault's address when executing", async () => {
+    const provider = program.provider;
+    const owner = anchor.web3.Keypair.generate();
+    const recipient = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    for (const label of ["operating", "reserve"]) {
+      await govern(multisig, multisigSigner, owner, "create_vault", { label });
+    }
+    const { vaults } = await program.account.multisig.fetch(multisig.publicKey);
+    assert.deepStrictEqual(
+      vaults.map((vault) => vault.label),
+      ["operating", "reserve"]
+    );
+    const [reserve] = await anchor.web3.PublicKey.findProgramAddress(
+      [multisig.publicKey.toBuffer(), Buffer.from("vault"), Buffer.from([1])],
+      program.programId
+    );
+
+    // Fund the reserve, then propose paying out of it.
+    const funding = new anchor.web3.Transaction().add(
+      anchor.web3.SystemProgram.transfer({
+        fromPubkey: provider.wallet.publicKey,
+        toPubkey: reserve,
+        lamports: 2000000,
+      })
+    );
+    await provider.send(funding);
+    const ix = anchor.web3.SystemProgram.transfer({
+      fromPubkey: reserve,
+      toPubkey: recipient.publicKey,
+      lamports: 1000000,
+    });
+    const transaction = await createTransaction(
+      multisig,
+      owner,
+      ix.programId,
+      ix.keys,
+      ix.data
+    );
+    // The program signs for the vault, so it's passed as a non-signer.
+    const accounts = ix.keys.map((meta) => ({ ...meta, isSigner: false }));
+    const payOut = (vaultIndex) =>
+      executeTransaction(multisig, multisigSigner, transaction, accounts, {
+        pid: ix.programId,
+        vaultIndex,
+      });
+
+    await assert.rejects(payOut(0), "only the reserve can sign for itself");
+    await assertProgramError(
+      payOut(2),
+      "The multisig has no vault with the given index."
+    );
+    await payOut(1);
+    assert.strictEqual(
+      await provider.connection.getBalance(recipient.publicKey),
+      1000000
+    );
+  });
+
+  it("Weighs owners' votes by the weights given at creation", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const owners = [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey];
+    await assertProgramError(
+      createMultisig(owners, 3, 1000, false, undefined, [3, 1]),
+      "There must be one weight per owner."
+    );
+    await assertProgramError(
+      createMultisig(owners, 6, 1000, false, undefined, [3, 1, 1]),
+      "Threshold must be less than or equal to the number of owners."
+    );
+    const { multisig, multisigSigner } = await createMultisig(
+      owners,
+      3,
+      1000,
+      false,
+      undefined,
+      [3, 1, 1]
+    );
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const propose = (proposer, description) =>
+      createTransaction(
+        multisig,
+        proposer,
+        program.programId,
+        accounts,
+        program.coder.instruction.encode("rename", { description })
+      );
+
+    // B and C together weigh two, short of the threshold.
+    const small = await propose(ownerB, "small");
+    await approve(multisig, small, ownerC);
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, small, accounts),
+      "Not enough owners signed this transaction."
+    );
+
+    // A alone weighs three.
+    const large = await propose(ownerA, "large");
+    await executeTransaction(multisig, multisigSigner, large, accounts);
+    assert.strictEqual(
+      (await program.account.multisig.fetch(multisig.publicKey)).description,
+      "large"
+    );
+
+    // New owners come with new weights.
+    await executeTransaction(
+      multisig,
+      multisigSigner,
+      await createTransaction(
+        multisig,
+        ownerA,
+        program.programId,
+        accounts,
+        program.coder.instruction.encode("set_owners", {
+          owners: [ownerA.publicKey, ownerB.publicKey],
+          weights: [new anchor.BN(1), new anchor.BN(2)],
+        })
+      ),
+      accounts
+    );
+    const { weights, threshold } = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.deepStrictEqual(
+      weights.map((weight) => weight.toNumber()),
+      [1, 2]
+    );
+    assert.strictEqual(threshold.toNumber(), 3);
+  });
+
+  it("Audits the owners for duplicates and the multisig's own addresses", async () => {
+    const owner = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey, anchor.web3.Keypair.generate().publicKey],
+      1
+    );
+    const audit = async () => {
+      const simulation = await simulate(
+        program.instruction.auditOwners({
+          accounts: { multisig: multisig.publicKey },
+        }),
+        1
+      );
+      return { logs: simulation.raw, clean: simulation.returnData[0] };
+    };
+
+    let { logs, clean } = await audit();
+    assert.strictEqual(clean, 1);
+    assert.ok(logs.includes("Program log: Owner audit found 0 issues"));
+
+    // Duplicates can't be added, but the multisig's own signer can.
+    await govern(multisig, multisigSigner, owner, "add_owner", {
+      owner: multisigSigner,
+    });
+    ({ logs, clean } = await audit());
+    assert.strictEqual(clean, 0);
+    assert.ok(
+      logs.includes(`Program log: Off-curve owner 2: ${multisigSigner.toBase58()}`)
+    );
+    assert.ok(logs.includes("Program log: Owner audit found 1 issues"));
+  });
+
+  it("Removes a single owner, lowering an unreachable threshold", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      3
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const removeOwner = async (owner) => {
+      const transaction = await createTransaction(
+        multisig,
+        ownerA,
+        program.programId,
+        accounts,
+        program.coder.instruction.encode("remove_owner", { owner })
+      );
+      await approve(multisig, transaction, ownerB);
+      await approve(multisig, transaction, ownerC);
+      await executeTransaction(multisig, multisigSigner, transaction, accounts);
+    };
+
+    await assertProgramError(
+      removeOwner(anchor.web3.Keypair.generate().publicKey),
+      "The given owner is not part of this multisig."
+    );
+    await removeOwner(ownerC.publicKey);
+    const { owners, threshold, ownerSetSeqno } =
+      await program.account.multisig.fetch(multisig.publicKey);
+    assert.deepStrictEqual(owners, [ownerA.publicKey, ownerB.publicKey]);
+    assert.strictEqual(threshold.toNumber(), 2);
+    assert.strictEqual(ownerSetSeqno, 1);
+  });
+
+  it("Delays threshold decreases but not increases", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_threshold_decrease_delay", {
+      delay: new anchor.BN(2),
+    });
+    const threshold = async () =>
+      (await program.account.multisig.fetch(multisig.publicKey)).threshold.toNumber();
+
+    await govern(multisig, multisigSigner, ownerA, "change_threshold", {
+      threshold: new anchor.BN(2),
+    });
+    assert.strictEqual(await threshold(), 2);
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("change_threshold", {
+        threshold: new anchor.BN(1),
+      })
+    );
+    await approve(multisig, transaction, ownerB);
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+    assert.strictEqual(await threshold(), 2);
+    assert.strictEqual(
+      (
+        await program.account.multisig.fetch(multisig.publicKey)
+      ).pendingThreshold.toNumber(),
+      1
+    );
+
+    const apply = () =>
+      program.rpc.applyThresholdChange({
+        accounts: { multisig: multisig.publicKey, owner: ownerC.publicKey },
+        signers: [ownerC],
+      });
+    await assertProgramError(
+      apply(),
+      "The threshold decrease delay hasn't passed yet."
+    );
+    await sleep(3000);
+    await apply();
+    assert.strictEqual(await threshold(), 1);
+    await assertProgramError(apply(), "No threshold decrease is pending.");
+  });
+
+  it("Delays threshold decreases through harden_governance too", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_threshold_decrease_delay", {
+      delay: new anchor.BN(2),
+    });
+    await govern(multisig, multisigSigner, ownerA, "change_threshold", {
+      threshold: new anchor.BN(2),
+    });
+
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("harden_governance", {
+        newThreshold: new anchor.BN(1),
+        minDelay: new anchor.BN(5),
+      })
+    );
+    await approve(multisig, transaction, ownerB);
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+
+    // The slot delay applies at once, the decrease only once applied.
+    let multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.strictEqual(multisigAccount.threshold.toNumber(), 2);
+    assert.strictEqual(multisigAccount.pendingThreshold.toNumber(), 1);
+    assert.strictEqual(multisigAccount.minSlotDelay.toNumber(), 5);
+
+    const apply = () =>
+      program.rpc.applyThresholdChange({
+        accounts: { multisig: multisig.publicKey, owner: ownerC.publicKey },
+        signers: [ownerC],
+      });
+    await assertProgramError(
+      apply(),
+      "The threshold decrease delay hasn't passed yet."
+    );
+    await sleep(3000);
+    await apply();
+    multisigAccount = await program.account.multisig.fetch(multisig.publicKey);
+    assert.strictEqual(multisigAccount.threshold.toNumber(), 1);
+  });
+
+  it("Refuses a zero threshold", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      1
+    );
+    await assertProgramError(
+      govern(multisig, multisigSigner, ownerA, "change_threshold", {
+        threshold: new anchor.BN(0),
+      }),
+      "Threshold must be less than or equal to the number of owners."
+    );
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.strictEqual(multisigAccount.threshold.toNumber(), 1);
+  });
+
+  it("Recovers tokens sent to the multisig signer by mistake", async () => {
+    const provider = program.provider;
+    const owner = provider.wallet.payer;
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    const mint = await Token.createMint(
+      provider.connection,
+      owner,
+      owner.publicKey,
+      null,
+      0,
+      TOKEN_PROGRAM_ID
+    );
+    const stray = await mint.createAccount(multisigSigner);
+    await mint.mintTo(stray, owner, [], 7);
+    const destination = await mint.createAccount(owner.publicKey);
+    const recover = (tokenAccount, mintKey) =>
+      govern(
+        multisig,
+        multisigSigner,
+        owner,
+        "recover_token",
+        { mint: mintKey },
+        [
+          { pubkey: tokenAccount, isWritable: true, isSigner: false },
+          { pubkey: destination, isWritable: true, isSigner: false },
+          { pubkey: TOKEN_PROGRAM_ID, isWritable: false, isSigner: false },
+        ]
+      );
+
+    // Only the signer's own accounts of the given mint qualify.
+    await assertProgramError(
+      recover(destination, mint.publicKey),
+      "The token account isn't held by the multisig signer for the given mint."
+    );
+    await assertProgramError(
+      recover(stray, anchor.web3.Keypair.generate().publicKey),
+      "The token account isn't held by the multisig signer for the given mint."
+    );
+
+    await recover(stray, mint.publicKey);
+    assert.strictEqual((await mint.getAccountInfo(stray)).amount.toNumber(), 0);
+    assert.strictEqual(
+      (await mint.getAccountInfo(destination)).amount.toNumber(),
+      7
+    );
+  });
+
+  it("Tracks the status of a transaction", async () => {
+    const owner = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      owner,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("rename", { description: "status" })
+    );
+    const status = async () =>
+      (await program.account.transaction.fetch(transaction.publicKey)).status;
+
+    assert.deepStrictEqual(await status(), { pending: {} });
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+    assert.deepStrictEqual(await status(), { executed: {} });
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts),
+      "The given transaction has already been executed."
+    );
+  });
+
+  it("Dumps the exact instruction execute_transaction invokes", async () => {
+    const owner = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    // Proposed without flagging the multisig signer, which execution does.
+    const accounts = authAccounts(multisig, multisigSigner).map((meta) => ({
+      ...meta,
+      isSigner: false,
+    }));
+    const data = program.coder.instruction.encode("rename", {
+      description: "dumped",
+    });
+    const transaction = await createTransaction(
+      multisig,
+      owner,
+      program.programId,
+      accounts,
+      data
+    );
+
+    const u32 = (n) => {
+      const buf = Buffer.alloc(4);
+      buf.writeUInt32LE(n);
+      return buf;
+    };
+    const expected = Buffer.concat([
+      u32(1),
+      program.programId.toBuffer(),
+      u32(2),
+      multisig.publicKey.toBuffer(),
+      Buffer.from([0, 1]),
+      multisigSigner.toBuffer(),
+      Buffer.from([1, 0]),
+      u32(data.length),
+      data,
+    ]);
+    const simulation = await simulate(
+      program.instruction.dumpCpiPayload(0, {
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: transaction.publicKey,
+        },
+      }),
+      expected.length
+    );
+    assert.deepStrictEqual(simulation.returnData, expected);
+    assert.ok(
+      simulation.raw.includes(`Program log: cpi payload: ${expected.length} bytes`)
+    );
+  });
+
+  it("Cancels a pending transaction through governance", async () => {
+    const owner = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const propose = () =>
+      createTransaction(
+        multisig,
+        owner,
+        program.programId,
+        accounts,
+        program.coder.instruction.encode("rename", { description: "unwanted" })
+      );
+    const cancel = (target) =>
+      govern(multisig, multisigSigner, owner, "cancel_transaction", {}, [
+        { pubkey: target.publicKey, isWritable: true, isSigner: false },
+      ]);
+
+    const unwanted = await propose();
+    await cancel(unwanted);
+    assert.deepStrictEqual(
+      (await program.account.transaction.fetch(unwanted.publicKey)).status,
+      { cancelled: {} }
+    );
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, unwanted, accounts),
+      "The transaction has been cancelled."
+    );
+    await assertProgramError(
+      cancel(unwanted),
+      "The transaction has been cancelled."
+    );
+
+    const executed = await propose();
+    await executeTransaction(multisig, multisigSigner, executed, accounts);
+    await assertProgramError(
+      cancel(executed),
+      "The given transaction has already been executed."
+    );
+  });
+
+  it("Approves and executes in one call once the threshold is reached", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      3
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("rename", { description: "at once" })
+    );
+    const approveAndExecute = (owner) =>
+      program.rpc.approveAndExecute({
+        accounts: {
+          multisig: multisig.publicKey,
+          multisigSigner,
+          transaction: transaction.publicKey,
+          owner: owner.publicKey,
+        },
+        remainingAccounts: accounts
+          .map((meta) =>
+            meta.pubkey.equals(multisigSigner)
+              ? { ...meta, isSigner: false }
+              : meta
+          )
+          .concat({
+            pubkey: program.programId,
+            isWritable: false,
+            isSigner: false,
+          }),
+        signers: [owner],
+      });
+
+    // Short of the threshold, only the approval is recorded.
+    await approveAndExecute(ownerB);
+    let txAccount = await program.account.transaction.fetch(
+      transaction.publicKey
+    );
+    assert.deepStrictEqual(signedBy(txAccount), [true, true, false]);
+    assert.ok(!txAccount.didExecute);
+
+    await approveAndExecute(ownerC);
+    txAccount = await program.account.transaction.fetch(transaction.publicKey);
+    assert.ok(txAccount.didExecute);
+    assert.ok(txAccount.executor.equals(ownerC.publicKey));
+    assert.strictEqual(
+      (await program.account.multisig.fetch(multisig.publicKey)).description,
+      "at once"
+    );
+  });
+
+  it("Blocks a transaction from reentering its own execution", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey],
+      1
+    );
+    // The proposal executes itself again, with the multisig signer as the
+    // executor of the nested call.
+    const transaction = anchor.web3.Keypair.generate();
+    const accounts = [
+      { pubkey: multisig.publicKey, isWritable: false, isSigner: false },
+      { pubkey: multisigSigner, isWritable: false, isSigner: true },
+      { pubkey: transaction.publicKey, isWritable: true, isSigner: false },
+      { pubkey: multisigSigner, isWritable: false, isSigner: true },
+    ];
+    await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("execute_transaction", {
+        profile: false,
+        vaultIndex: null,
+      }),
+      1000,
+      transaction
+    );
+
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts),
+      "The given transaction has already been executed."
+    );
+    const txAccount = await program.account.transaction.fetch(
+      transaction.publicKey
+    );
+    assert.ok(!txAccount.didExecute);
+  });
+
+  it("Deposits and withdraws tokens through the multisig signer", async () => {
+    const provider = program.provider;
+    const owner = provider.wallet.payer;
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    const mint = await Token.createMint(
+      provider.connection,
+      owner,
+      owner.publicKey,
+      null,
+      0,
+      TOKEN_PROGRAM_ID
+    );
+    const source = await mint.createAccount(owner.publicKey);
+    await mint.mintTo(source, owner, [], 10);
+    const vault = await mint.createAccount(multisigSigner);
+    const deposit = (to) =>
+      program.rpc.depositToken(new anchor.BN(6), {
+        accounts: {
+          multisig: multisig.publicKey,
+          multisigSigner,
+          mint: mint.publicKey,
+          source,
+          vault: to,
+          owner: owner.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      });
+
+    // Deposits only go to the multisig signer's accounts.
+    await assertProgramError(
+      deposit(source),
+      "The token account isn't held by the multisig signer for the given mint."
+    );
+    await deposit(vault);
+    assert.strictEqual((await mint.getAccountInfo(vault)).amount.toNumber(), 6);
+
+    await govern(
+      multisig,
+      multisigSigner,
+      owner,
+      "withdraw_token",
+      { amount: new anchor.BN(4) },
+      [
+        { pubkey: mint.publicKey, isWritable: false, isSigner: false },
+        { pubkey: vault, isWritable: true, isSigner: false },
+        { pubkey: source, isWritable: true, isSigner: false },
+        { pubkey: TOKEN_PROGRAM_ID, isWritable: false, isSigner: false },
+      ]
+    );
+    assert.strictEqual((await mint.getAccountInfo(vault)).amount.toNumber(), 2);
+    assert.strictEqual(
+      (await mint.getAccountInfo(source)).amount.toNumber(),
+      8
+    );
+  });
+
+  it("Closes a multisig only once its escrow is empty", async () => {
+    const provider = program.provider;
+    const owner = provider.wallet.payer;
+    const destination = anchor.web3.Keypair.generate();
+    const closeMultisig = (multisig, multisigSigner) =>
+      govern(multisig, multisigSigner, owner, "close_multisig", {}, [
+        { pubkey: destination.publicKey, isWritable: true, isSigner: false },
+      ]);
+
+    const funded = await createMultisig([owner.publicKey], 1);
+    await program.rpc.depositLamports(new anchor.BN(1000), {
+      accounts: {
+        multisig: funded.multisig.publicKey,
+        owner: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      },
+    });
+    await assertProgramError(
+      closeMultisig(funded.multisig, funded.multisigSigner),
+      "The escrow still holds lamports."
+    );
+
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    const rent = await provider.connection.getBalance(multisig.publicKey);
+    await closeMultisig(multisig, multisigSigner);
+    assert.strictEqual(
+      await provider.connection.getAccountInfo(multisig.publicKey),
+      null
+    );
+    assert.strictEqual(
+      await provider.connection.getBalance(destination.publicKey),
+      rent
+    );
+  });
+
+  it("Lets the proposer raise a transaction's threshold", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      2
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const data = program.coder.instruction.encode("change_threshold", {
+      threshold: new anchor.BN(3),
+    });
+    const propose = async (thresholdOverride) => {
+      const transaction = anchor.web3.Keypair.generate();
+      await program.rpc.createTransaction(
+        program.programId,
+        accounts,
+        data,
+        thresholdOverride,
+        {
+          accounts: {
+            multisig: multisig.publicKey,
+            transaction: transaction.publicKey,
+            proposer: ownerA.publicKey,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          },
+          instructions: [
+            await program.account.transaction.createInstruction(
+              transaction,
+              1000
+            ),
+          ],
+          signers: [transaction, ownerA],
+        }
+      );
+      return transaction;
+    };
+
+    // Overrides can't go below the multisig's threshold or out of reach.
+    await assertProgramError(
+      propose(new anchor.BN(1)),
+      "Threshold must be less than or equal to the number of owners."
+    );
+    await assertProgramError(
+      propose(new anchor.BN(4)),
+      "Threshold must be less than or equal to the number of owners."
+    );
+
+    const transaction = await propose(new anchor.BN(3));
+    await approve(multisig, transaction, ownerB);
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts),
+      "Not enough owners signed this transaction."
+    );
+    await approve(multisig, transaction, ownerC);
+    await executeTransaction(multisig, multisigSigner, transaction, accounts);
+  });
+
+  it("Caps escrow withdrawals per spending period", async () => {
+    const provider = program.provider;
+    const owner = provider.wallet.payer;
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey],
+      1
+    );
+    await program.rpc.depositLamports(new anchor.BN(3000000), {
+      accounts: {
+        multisig: multisig.publicKey,
+        owner: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      },
+    });
+    await govern(multisig, multisigSigner, owner, "set_spending_limit", {
+      spendingLimit: new anchor.BN(1500000),
+      spendingPeriod: new anchor.BN(3600),
+    });
+    const destination = anchor.web3.Keypair.generate().publicKey;
+    const withdraw = () =>
+      govern(
+        multisig,
+        multisigSigner,
+        owner,
+        "withdraw_lamports_to",
+        { amount: new anchor.BN(1000000), reference: noReference },
+        [{ pubkey: destination, isWritable: true, isSigner: false }]
+      );
+
+    await withdraw();
+    await assertProgramError(
+      withdraw(),
+      "The withdrawal would exceed the spending limit for the period."
+    );
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.ok(multisigAccount.spentInPeriod.eq(new anchor.BN(1000000)));
+    assert.strictEqual(
+      await provider.connection.getBalance(destination),
+      1000000
+    );
+  });
+
+  it("Restricts transactions to the allowed programs", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey],
+      1
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_allowed_programs", {
+      programs: [TOKEN_PROGRAM_ID],
+    });
+    const propose = (pid) =>
+      createTransaction(multisig, ownerA, pid, [], Buffer.from([1, 2, 3]));
+
+    await assertProgramError(
+      propose(anchor.web3.SystemProgram.programId),
+      "The multisig doesn't allow transactions targeting this program."
+    );
+    await propose(TOKEN_PROGRAM_ID);
+    // The multisig itself stays reachable, to lift the restriction.
+    await govern(multisig, multisigSigner, ownerA, "set_allowed_programs", {
+      programs: [],
+    });
+    await propose(anchor.web3.SystemProgram.programId);
+  });
+
+  it("Logs how many approvals a transaction still needs", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey],
+      3
+    );
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      authAccounts(multisig, multisigSigner),
+      program.coder.instruction.encode("change_threshold", {
+        threshold: new anchor.BN(2),
+      })
+    );
+    const remaining = async () => {
+      const simulation = await program.simulate.getApprovalsRemaining({
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: transaction.publicKey,
+        },
+      });
+      return simulation.raw.find((log) =>
+        log.startsWith("Program log: signed: ")
+      );
+    };
+
+    assert.strictEqual(
+      await remaining(),
+      "Program log: signed: 1 remaining: 2"
+    );
+    await approve(multisig, transaction, ownerB);
+    assert.strictEqual(
+      await remaining(),
+      "Program log: signed: 2 remaining: 1"
+    );
+  });
+
+  it("Returns the outcome of an execution", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      2
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const transaction = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("change_threshold", {
+        threshold: new anchor.BN(1),
+      })
+    );
+    await approve(multisig, transaction, ownerB);
+
+    const simulation = await simulate(
+      program.instruction.executeTransaction(false, null, {
+        accounts: {
+          multisig: multisig.publicKey,
+          multisigSigner,
+          transaction: transaction.publicKey,
+          executor: program.provider.wallet.publicKey,
+        },
+        remainingAccounts: accounts
+          .map((meta) =>
+            meta.pubkey.equals(multisigSigner)
+              ? { ...meta, isSigner: false }
+              : meta
+          )
+          .concat({
+            pubkey: program.programId,
+            isWritable: false,
+            isSigner: false,
+          }),
+      }),
+      17
+    );
+    const result = program.coder.types.decode(
+      "ExecutionResult",
+      simulation.returnData
+    );
+    assert.ok(result.executed);
+    assert.ok(result.signatures.eq(new anchor.BN(2)));
+    assert.ok(result.threshold.eq(new anchor.BN(2)));
+
+    // The executed transaction carries the same result in its meta, where a
+    // CPI caller's get_return_data reads it too.
+    const signature = await executeTransaction(
+      multisig,
+      multisigSigner,
+      transaction,
+      accounts
+    );
+    const executed = program.coder.types.decode(
+      "ExecutionResult",
+      await transactionReturnData(signature, 17)
+    );
+    assert.ok(executed.executed);
+    assert.ok(executed.signatures.eq(new anchor.BN(2)));
+    assert.ok(executed.threshold.eq(new anchor.BN(2)));
+  });
+
+  it("Refuses proposals transferring lamports out of the multisig", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey],
+      1
+    );
+    const destination = anchor.web3.Keypair.generate().publicKey;
+    const proposeTransfer = (fromPubkey) => {
+      const ix = anchor.web3.SystemProgram.transfer({
+        fromPubkey,
+        toPubkey: destination,
+        lamports: 1000,
+      });
+      return createTransaction(
+        multisig,
+        ownerA,
+        ix.programId,
+        ix.keys,
+        ix.data
+      );
+    };
+
+    await assertProgramError(
+      proposeTransfer(multisigSigner),
+      "Lamports must be moved out of the multisig through its withdraw instructions."
+    );
+    await assertProgramError(
+      proposeTransfer(multisig.publicKey),
+      "Lamports must be moved out of the multisig through its withdraw instructions."
+    );
+    await proposeTransfer(ownerA.publicKey);
+  });
+
+  it("Records the owner set and threshold a transaction executed under", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      1
+    );
+    const transaction = await govern(
+      multisig,
+      multisigSigner,
+      ownerA,
+      "set_min_slot_delay",
+      { minSlotDelay: new anchor.BN(0) }
+    );
+    // Later changes leave the record alone.
+    await govern(multisig, multisigSigner, ownerA, "set_owners", {
+      owners: [ownerA.publicKey],
+      weights: [],
+    });
+
+    const txAccount = await program.account.transaction.fetch(
+      transaction.publicKey
+    );
+    assert.deepStrictEqual(
+      txAccount.executedOwners.map((owner) => owner.toString()),
+      [ownerA.publicKey.toString(), ownerB.publicKey.toString()]
+    );
+    assert.ok(txAccount.executedThreshold.eq(new anchor.BN(1)));
+  });
+
+  it("Refuses withdrawals to an outdated owner list", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const rotated = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      1
+    );
+    const accounts = authAccounts(
+      multisig,
+      multisigSigner,
+      [ownerA, ownerB].map((owner) => ({
+        pubkey: owner.publicKey,
+        isWritable: true,
+        isSigner: false,
+      }))
+    );
+    const withdrawal = await createTransaction(
+      multisig,
+      ownerA,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("withdraw_lamports", {
+        reference: noReference,
+      })
+    );
+
+    // Replacing an owner keeps the owner set seqno, so only the list of
+    // destinations gives the proposal away.
+    await govern(
+      multisig,
+      multisigSigner,
+      ownerA,
+      "replace_owner_keep_approvals",
+      { old: ownerB.publicKey, new: rotated.publicKey, acknowledgeRisk: true }
+    );
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, withdrawal, accounts),
+      "The accounts given don't match the current owners, in order."
+    );
+  });
+
+  it("Validates proposals without creating them", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const outsider = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey],
+      1
+    );
+    const validate = async (proposer, ix) => {
+      const simulation = await simulate(
+        program.instruction.validateProposal(ix.programId, ix.keys, ix.data, {
+          accounts: {
+            multisig: multisig.publicKey,
+            proposer: proposer.publicKey,
+          },
+        }),
+        5
+      );
+      return simulation.returnData;
+    };
+    // None, padded like the error codes.
+    const valid = Buffer.alloc(5);
+    const errorCode = (name) => {
+      const code = Buffer.alloc(5);
+      code[0] = 1;
+      code.writeUInt32LE(
+        program.idl.errors.find((error) => error.name === name).code,
+        1
+      );
+      return code;
+    };
+    const transfer = (fromPubkey) =>
+      anchor.web3.SystemProgram.transfer({
+        fromPubkey,
+        toPubkey: ownerA.publicKey,
+        lamports: 1000,
+      });
+
+    assert.deepStrictEqual(
+      await validate(ownerA, transfer(multisigSigner)),
+      errorCode("UseWithdrawInstruction")
+    );
+    assert.deepStrictEqual(
+      await validate(outsider, transfer(outsider.publicKey)),
+      errorCode("InvalidOwner")
+    );
+    assert.deepStrictEqual(
+      await validate(ownerA, transfer(ownerA.publicKey)),
+      valid
+    );
+  });
+
+  it("Lets a single owner hold a transaction for review", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      1
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const propose = () =>
+      createTransaction(
+        multisig,
+        ownerA,
+        program.programId,
+        accounts,
+        program.coder.instruction.encode("rename", { description: "held" })
+      );
+    const flag = (transaction) =>
+      program.rpc.flagForReview({
+        accounts: {
+          multisig: multisig.publicKey,
+          transaction: transaction.publicKey,
+          owner: ownerB.publicKey,
+        },
+        signers: [ownerB],
+      });
+
+    const early = await propose();
+    await assertProgramError(
+      flag(early),
+      "The multisig has no review period to flag transactions for."
+    );
+    await govern(multisig, multisigSigner, ownerA, "set_review_period", {
+      reviewPeriod: new anchor.BN(3600),
+    });
+
+    const transaction = await propose();
+    await flag(transaction);
+    await assertProgramError(
+      flag(transaction),
+      "The transaction was already flagged for review."
+    );
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts),
+      "The transaction is held for review."
+    );
+  });
+
+  it("Proposes owner and threshold changes without hand-encoding them", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const ownerC = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      1
+    );
+
+    const propose = async (name, ...args) => {
+      const transaction = anchor.web3.Keypair.generate();
+      await program.rpc[name](...args, {
+        accounts: {
+          multisig: multisig.publicKey,
+          multisigSigner,
+          transaction: transaction.publicKey,
+          proposer: ownerA.publicKey,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        },
+        instructions: [
+          await program.account.transaction.createInstruction(
+            transaction,
+            1000
+          ),
+        ],
+        signers: [transaction, ownerA],
+      });
+      const txAccount = await program.account.transaction.fetch(
+        transaction.publicKey
+      );
+      assert.ok(txAccount.programId.equals(program.programId));
+      await executeTransaction(
+        multisig,
+        multisigSigner,
+        transaction,
+        txAccount.accounts
+      );
+    };
+
+    const newOwners = [ownerA.publicKey, ownerB.publicKey, ownerC.publicKey];
+    await propose("proposeSetOwners", newOwners);
+    await propose("proposeChangeThreshold", new anchor.BN(2));
+
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.deepStrictEqual(multisigAccount.owners, newOwners);
+    assert.strictEqual(multisigAccount.threshold.toNumber(), 2);
+  });
+
+  it("Conserves lamports when splitting the escrow among owners", async () => {
+    const connection = program.provider.connection;
+    // Shares stay above the rent exemption of the owners' empty accounts,
+    // with a remainder for the first owner. split_escrow's unit tests cover
+    // the other owner counts and pool sizes.
+    const count = 3;
+    const pool = count * 1000000 + 2;
+    const owners = [...Array(count)].map(() => anchor.web3.Keypair.generate());
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(
+        owners[0].publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+    const { multisig, multisigSigner } = await createMultisig(
+      owners.map((owner) => owner.publicKey),
+      1
+    );
+    await program.rpc.depositLamports(new anchor.BN(pool), {
+      accounts: {
+        multisig: multisig.publicKey,
+        owner: owners[0].publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      },
+      signers: [owners[0]],
+    });
+
+    const keys = [multisig.publicKey].concat(
+      owners.map((owner) => owner.publicKey)
+    );
+    const balances = async () => {
+      const result = [];
+      for (const key of keys) {
+        result.push(await connection.getBalance(key));
+      }
+      return result;
+    };
+    const before = await balances();
+    await govern(
+      multisig,
+      multisigSigner,
+      owners[0],
+      "withdraw_lamports",
+      { reference: noReference },
+      owners.map((owner) => ({
+        pubkey: owner.publicKey,
+        isWritable: true,
+        isSigner: false,
+      }))
+    );
+    const [escrowDelta, ...credits] = (await balances()).map(
+      (balance, index) => balance - before[index]
+    );
+
+    // Whatever leaves the escrow reaches the owners, split evenly.
+    assert.strictEqual(escrowDelta, -pool);
+    assert.deepStrictEqual(credits, [1000002, 1000000, 1000000]);
+  });
+
+  it("Pins proposals to the target program's deployment", async () => {
+    const ownerA = anchor.web3.Keypair.generate();
+    const ownerB = anchor.web3.Keypair.generate();
+    const { multisig, multisigSigner } = await createMultisig(
+      [ownerA.publicKey, ownerB.publicKey],
+      1
+    );
+    const [programData] = await anchor.web3.PublicKey.findProgramAddress(
+      [program.programId.toBuffer()],
+      new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    );
+    const accounts = authAccounts(multisig, multisigSigner);
+    const data = program.coder.instruction.encode("change_threshold", {
+      threshold: new anchor.BN(2),
+    });
+    const propose = async (pinned) => {
+      const transaction = anchor.web3.Keypair.generate();
+      await program.rpc.createTransaction(
+        program.programId,
+        accounts,
+        data,
+        null,
+        {
+          accounts: {
+            multisig: multisig.publicKey,
+            transaction: transaction.publicKey,
+            proposer: ownerA.publicKey,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          },
+          remainingAccounts: [
+            { pubkey: pinned, isWritable: false, isSigner: false },
+          ],
+          instructions: [
+            await program.account.transaction.createInstruction(
+              transaction,
+              1000
+            ),
+          ],
+          signers: [transaction, ownerA],
+        }
+      );
+      return transaction;
+    };
+
+    await assertProgramError(
+      propose(multisig.publicKey),
+      "The account isn't the target program's program data account."
+    );
+
+    const transaction = await propose(programData);
+    const txAccount = await program.account.transaction.fetch(
+      transaction.publicKey
+    );
+    assert.ok(txAccount.targetProgramSlot.toNumber() > 0);
+
+    // Execution needs the program data account to check the deployment.
+    await assertProgramError(
+      executeTransaction(multisig, multisigSigner, transaction, accounts),
+      "The account isn't the target program's program data account."
+    );
+    await executeTransaction(multisig, multisigSigner, transaction, accounts, {
+      extraAccounts: [
+        { pubkey: programData, isWritable: false, isSigner: false },
+      ],
+    });
+    const multisigAccount = await program.account.multisig.fetch(
+      multisig.publicKey
+    );
+    assert.strictEqual(multisigAccount.threshold.toNumber(), 2);
+  });
+
+  it("Emits an event for each state change", async () => {
+    const owner = program.provider.wallet.payer;
+    const ownerB = anchor.web3.Keypair.generate();
+    const BN = (value) => new anchor.BN(value);
+    const reference = Array(32).fill(1);
+
+    // Governed setters, the event each emits and the fields it should carry.
+    const settings = [
+      [
+        "set_approval_throttle",
+        { maxApprovalsPerWindow: BN(100), approvalWindow: BN(60) },
+        "ApprovalThrottleChanged",
+        {
+          oldMaxApprovalsPerWindow: 0,
+          oldApprovalWindow: 0,
+          maxApprovalsPerWindow: 100,
+          approvalWindow: 60,
+        },
+      ],
+      [
+        "set_spending_limit",
+        { spendingLimit: BN(5000000), spendingPeriod: BN(60) },
+        "SpendingLimitChanged",
+        {
+          oldSpendingLimit: 0,
+          oldSpendingPeriod: 0,
+          spendingLimit: 5000000,
+          spendingPeriod: 60,
+        },
+      ],
+      [
+        "set_emergency_policy",
+        { threshold: BN(1), delay: BN(60) },
+        "EmergencyPolicyChanged",
+        { oldThreshold: 1, oldDelay: 7 * 24 * 60 * 60, threshold: 1, delay: 60 },
+      ],
+      [
+        "set_threshold_decrease_delay",
+        { delay: BN(60) },
+        "ThresholdDecreaseDelayChanged",
+        { oldDelay: 0, delay: 60 },
+      ],
+      [
+        "set_min_reserve",
+        { minReserve: BN(5) },
+        "MinReserveChanged",
+        { oldMinReserve: 0, minReserve: 5 },
+      ],
+      [
+        "set_withdraw_reference_policy",
+        { required: false, references: [reference] },
+        "WithdrawReferencePolicyChanged",
+        {
+          oldRequired: false,
+          oldReferences: [],
+          required: false,
+          references: [reference],
+        },
+      ],
+      [
+        "set_allowed_programs",
+        { programs: [program.programId] },
+        "AllowedProgramsChanged",
+        { oldPrograms: [], programs: [program.programId] },
+      ],
+      [
+        "set_pause_thresholds",
+        { pauseThreshold: BN(1), unpauseThreshold: BN(2) },
+        "PauseThresholdsChanged",
+        {
+          oldPauseThreshold: 0,
+          oldUnpauseThreshold: 0,
+          pauseThreshold: 1,
+          unpauseThreshold: 2,
+        },
+      ],
+      [
+        "set_invalidate_on_tamper",
+        { invalidate: true },
+        "InvalidateOnTamperChanged",
+        { oldInvalidate: false, invalidate: true },
+      ],
+      [
+        "set_max_mint_amount",
+        { maxMintAmount: BN(7) },
+        "MaxMintAmountChanged",
+        { oldMaxMintAmount: 0, maxMintAmount: 7 },
+      ],
+      [
+        "set_reweight_invalidates",
+        { invalidates: true },
+        "ReweightInvalidatesChanged",
+        { oldInvalidates: false, invalidates: true },
+      ],
+      [
+        "set_weight_by_contribution",
+        { enabled: false },
+        "WeightByContributionChanged",
+        { oldEnabled: false, enabled: false },
+      ],
+      [
+        "set_reject_trivial_data",
+        { enabled: true },
+        "RejectTrivialDataChanged",
+        { oldEnabled: false, enabled: true },
+      ],
+      [
+        "set_add_owner_requires_unanimous",
+        { enabled: true },
+        "AddOwnerRequiresUnanimousChanged",
+        { oldEnabled: false, enabled: true },
+      ],
+      [
+        "set_owner_groups",
+        { ownerGroups: Buffer.from([0, 1]), minGroups: 1 },
+        "OwnerGroupsChanged",
+        { oldOwnerGroups: [], oldMinGroups: 0, ownerGroups: [0, 1], minGroups: 1 },
+      ],
+      [
+        "set_transaction_lifetime",
+        { lifetime: BN(3600), expiryFrom: 0 },
+        "TransactionLifetimeChanged",
+        { oldLifetime: 0, oldExpiryFrom: 0, lifetime: 3600, expiryFrom: 0 },
+      ],
+      [
+        "set_max_proposal_age",
+        { maxProposalAge: BN(3600) },
+        "MaxProposalAgeChanged",
+        { oldMaxProposalAge: 0, maxProposalAge: 3600 },
+      ],
+      [
+        "set_clamp_threshold_to_active",
+        { enabled: true },
+        "ClampThresholdToActiveChanged",
+        { oldEnabled: false, enabled: true },
+      ],
+      [
+        "set_reject_threshold",
+        { rejectThreshold: BN(2) },
+        "RejectThresholdChanged",
+        { oldRejectThreshold: 0, rejectThreshold: 2 },
+      ],
+      [
+        "set_executor_must_be_owner",
+        { enabled: true },
+        "ExecutorMustBeOwnerChanged",
+        { oldEnabled: false, enabled: true },
+      ],
+      [
+        "set_emit_per_approval",
+        { enabled: false },
+        "EmitPerApprovalChanged",
+        { oldEnabled: true, enabled: false },
+      ],
+      [
+        "set_proposer_cannot_execute",
+        { enabled: false },
+        "ProposerCannotExecuteChanged",
+        { oldEnabled: false, enabled: false },
+      ],
+      [
+        "set_protect_executor",
+        { enabled: true },
+        "ProtectExecutorChanged",
+        { oldEnabled: false, enabled: true },
+      ],
+      [
+        "set_review_period",
+        { reviewPeriod: BN(60) },
+        "ReviewPeriodChanged",
+        { oldReviewPeriod: 0, reviewPeriod: 60 },
+      ],
+    ];
+
+    const names = [
+      ...settings.map(([, , name]) => name),
+      "ThresholdDecreaseScheduled",
+      "MultisigClosed",
+      "MultisigCreated",
+      "LamportsDeposited",
+      "ApprovalRevoked",
+      "TransactionFrozen",
+      "TransactionCancelled",
+      "ExecutionDelaysChanged",
+    ];
+    const events = {};
+    const listeners = names.map((name) => {
+      events[name] = [];
+      return program.addEventListener(name, (event) =>
+        events[name].push(event)
+      );
+    });
+
+    const { multisig, multisigSigner } = await createMultisig(
+      [owner.publicKey, ownerB.publicKey],
+      1
+    );
+    await program.rpc.depositLamports(new anchor.BN(1000000), {
+      accounts: {
+        multisig: multisig.publicKey,
+        owner: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      },
+    });
+
+    const target = await createTransaction(
+      multisig,
+      owner,
+      program.programId,
+      authAccounts(multisig, multisigSigner),
+      program.coder.instruction.encode("rename", { description: "evented" })
+    );
+    await approve(multisig, target, ownerB);
+    await program.rpc.revoke({
+      accounts: {
+        multisig: multisig.publicKey,
+        transaction: target.publicKey,
+        owner: ownerB.publicKey,
+      },
+      signers: [ownerB],
+    });
+    const targetMeta = [
+      { pubkey: target.publicKey, isWritable: true, isSigner: false },
+    ];
+    await govern(
+      multisig,
+      multisigSigner,
+      owner,
+      "freeze_transaction",
+      {},
+      targetMeta
+    );
+    await govern(
+      multisig,
+      multisigSigner,
+      owner,
+      "cancel_transaction",
+      {},
+      targetMeta
+    );
+    await govern(multisig, multisigSigner, owner, "set_min_slot_delay", {
+      minSlotDelay: new anchor.BN(0),
+    });
+    for (const [name, args] of settings) {
+      await govern(multisig, multisigSigner, owner, name, args);
+    }
+
+    // With a threshold decrease delay set, lowering the threshold is only
+    // scheduled.
+    await govern(multisig, multisigSigner, owner, "change_threshold", {
+      threshold: BN(2),
+    });
+    const accounts = authAccounts(multisig, multisigSigner);
+    const lower = await createTransaction(
+      multisig,
+      owner,
+      program.programId,
+      accounts,
+      program.coder.instruction.encode("change_threshold", {
+        threshold: BN(1),
+      })
+    );
+    await approve(multisig, lower, ownerB);
+    await executeTransaction(multisig, multisigSigner, lower, accounts);
+
+    const closed = await createMultisig([owner.publicKey], 1);
+    const destination = anchor.web3.Keypair.generate().publicKey;
+    await govern(
+      closed.multisig,
+      closed.multisigSigner,
+      owner,
+      "close_multisig",
+      {},
+      [{ pubkey: destination, isWritable: true, isSigner: false }]
+    );
+
+    // Logs reach the listeners asynchronously.
+    for (let i = 0; i < 20 && names.some((name) => !events[name].length); i++) {
+      await sleep(500);
+    }
+    for (const listener of listeners) {
+      await program.removeEventListener(listener);
+    }
+
+    const mine = (name) =>
+      events[name].filter((event) => event.multisig.equals(multisig.publicKey));
+    const [created] = mine("MultisigCreated");
+    assert.deepStrictEqual(created.owners, [owner.publicKey, ownerB.publicKey]);
+    assert.strictEqual(created.threshold.toNumber(), 1);
+    assert.strictEqual(created.description, "test multisig");
+
+    const [deposited] = mine("LamportsDeposited");
+    assert.ok(deposited.depositor.equals(owner.publicKey));
+    assert.strictEqual(deposited.amount.toNumber(), 1000000);
+    assert.strictEqual(deposited.escrow.toNumber(), 1000000);
+
+    const [revoked] = mine("ApprovalRevoked");
+    assert.ok(revoked.transaction.equals(target.publicKey));
+    assert.ok(revoked.owner.equals(ownerB.publicKey));
+    assert.strictEqual(revoked.rejected, false);
+    assert.strictEqual(revoked.approvalWeight.toNumber(), 1);
+
+    const [frozen] = mine("TransactionFrozen");
+    assert.ok(frozen.transaction.equals(target.publicKey));
+    assert.strictEqual(frozen.frozen, true);
+
+    const [cancelled] = mine("TransactionCancelled");
+    assert.ok(cancelled.transaction.equals(target.publicKey));
+
+    const [delays] = mine("ExecutionDelaysChanged");
+    assert.strictEqual(delays.minSlotDelay.toNumber(), 0);
+    assert.strictEqual(delays.timelock.toNumber(), 0);
+    assert.strictEqual(delays.approveExecuteCooldown.toNumber(), 0);
+
+    const plain = (value) =>
+      anchor.BN.isBN(value)
+        ? value.toNumber()
+        : Buffer.isBuffer(value)
+        ? Array.from(value)
+        : Array.isArray(value)
+        ? value.map(plain)
+        : value;
+    for (const [, , name, fields] of settings) {
+      const [event] = mine(name);
+      assert.ok(event, `no ${name} event`);
+      for (const [field, expected] of Object.entries(fields)) {
+        assert.deepStrictEqual(plain(event[field]), expected, `${name}.${field}`);
+      }
+    }
+
+    const [scheduled] = mine("ThresholdDecreaseScheduled");
+    assert.strictEqual(scheduled.threshold.toNumber(), 2);
+    assert.strictEqual(scheduled.pendingThreshold.toNumber(), 1);
+    assert.ok(scheduled.readyAt.toNumber() > 0);
+
+    const [close] = events.MultisigClosed.filter((event) =>
+      event.multisig.equals(closed.multisig.publicKey)
+    );
+    assert.ok(close.destination.equals(destination));
+  });
 });